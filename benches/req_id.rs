@@ -0,0 +1,210 @@
+use tower_http_req_id::{IdGen, IdType, GenerateRequestId, ConstGenerator};
+
+use core::fmt;
+use core::pin::pin;
+use core::future::{Future, Ready, ready};
+use core::convert::Infallible;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::{self, RawWaker, RawWakerVTable, Waker};
+use std::sync::Arc;
+
+use http::{Request, Response};
+use tower::Service;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, black_box};
+
+//Same as `Waker::noop`, which requires newer Rust than `rust-version`.
+const NOOP_RAW_WAKER: RawWaker = RawWaker::new(core::ptr::null(), &NOOP_VTABLE);
+const NOOP_VTABLE: RawWakerVTable = RawWakerVTable::new(|_| NOOP_RAW_WAKER, |_| {}, |_| {}, |_| {});
+
+fn noop_waker() -> Waker {
+    //Safety: vtable functions do nothing, hence contract is upheld trivially.
+    unsafe {
+        Waker::from_raw(NOOP_RAW_WAKER)
+    }
+}
+
+#[derive(Clone)]
+struct StaticGenerator;
+
+impl IdGen<String> for StaticGenerator {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        "id".to_owned()
+    }
+}
+
+#[derive(Clone, Default)]
+struct CounterGenerator(Arc<AtomicU64>);
+
+impl IdGen<u64> for CounterGenerator {
+    #[inline(always)]
+    fn gen(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        //u64::MAX is 20 digits
+        20
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Inner;
+
+impl Service<Request<()>> for Inner {
+    type Response = Response<()>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    #[inline(always)]
+    fn poll_ready(&mut self, _: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+        task::Poll::Ready(Ok(()))
+    }
+
+    #[inline(always)]
+    fn call(&mut self, _: Request<()>) -> Self::Future {
+        ready(Ok(Response::new(())))
+    }
+}
+
+///Polls future, which is expected to complete immediately.
+fn complete<F: Future>(fut: F) -> F::Output {
+    let waker = noop_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+    match pin!(fut).poll(&mut ctx) {
+        task::Poll::Ready(result) => result,
+        task::Poll::Pending => unreachable!("Inner service never blocks"),
+    }
+}
+
+fn bench_service<G, O>(c: &mut Criterion, name: &str, gen: G)
+where
+    G: IdGen<O> + Clone + Send + Sync + 'static,
+    O: IdType<G> + Send + Sync + 'static,
+{
+    let mut svc = GenerateRequestId::<_, _, O>::new(Inner, gen);
+
+    let mut group = c.benchmark_group("service");
+    group.bench_function(BenchmarkId::new("call", name), |b| b.iter(|| {
+        let waker = noop_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+        let _ = svc.poll_ready(&mut ctx);
+        //Future is dropped without polling, hence response header is never written.
+        black_box(svc.call(Request::new(())));
+    }));
+    group.bench_function(BenchmarkId::new("call_poll", name), |b| b.iter(|| {
+        let waker = noop_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+        let _ = svc.poll_ready(&mut ctx);
+        black_box(complete(svc.call(Request::new(()))).unwrap());
+    }));
+    group.finish();
+}
+
+fn bench_client_id<G, O>(c: &mut Criterion, name: &str, mut svc: GenerateRequestId<Inner, G, O>, client_id: &'static str)
+where
+    G: IdGen<O> + Clone + Send + Sync + 'static,
+    O: IdType<G> + Send + Sync + 'static,
+{
+    let client_id = http::HeaderValue::from_static(client_id);
+
+    let mut group = c.benchmark_group("client_id");
+    group.bench_function(name, |b| b.iter(|| {
+        let mut req = Request::new(());
+        req.headers_mut().insert("x-request-id", black_box(client_id.clone()));
+
+        let waker = noop_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+        let _ = svc.poll_ready(&mut ctx);
+        black_box(svc.call(req));
+    }));
+    group.finish();
+}
+
+fn bench_format<G: IdGen<O>, O: fmt::Display>(c: &mut Criterion, name: &str, gen: G) {
+    let id = gen.gen();
+    let len_hint = gen.len_hint();
+
+    let mut group = c.benchmark_group("format");
+    group.bench_function(name, |b| b.iter(|| {
+        let mut buf = bytes::BytesMut::with_capacity(len_hint);
+        let _ = fmt::Write::write_fmt(&mut Writer(&mut buf), format_args!("{}", black_box(&id)));
+        black_box(buf.freeze());
+    }));
+    group.finish();
+}
+
+struct Writer<'a>(&'a mut bytes::BytesMut);
+
+impl fmt::Write for Writer<'_> {
+    #[inline(always)]
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.0.extend_from_slice(text.as_bytes());
+        Ok(())
+    }
+}
+
+fn generators(c: &mut Criterion) {
+    bench_service::<_, String>(c, "static", StaticGenerator);
+    bench_service::<_, String>(c, "const", ConstGenerator::new(StaticGenerator.gen()));
+    bench_service::<_, u64>(c, "counter", CounterGenerator::default());
+
+    bench_client_id(c, "string", GenerateRequestId::<_, _, String>::new(Inner, StaticGenerator), "0123456789abcdef0123456789abcdef");
+    bench_client_id(c, "string_infallible", GenerateRequestId::<_, _, String>::new_infallible(Inner, StaticGenerator), "0123456789abcdef0123456789abcdef");
+    bench_client_id(c, "counter", GenerateRequestId::<_, _, u64>::new(Inner, CounterGenerator::default()), "18446744073709551615");
+
+    bench_format::<_, String>(c, "static", StaticGenerator);
+    bench_format::<_, u64>(c, "counter", CounterGenerator::default());
+
+    {
+        use tower_http_req_id::AlwaysValidHeader;
+
+        let id = u64::MAX;
+        let mut group = c.benchmark_group("header_value");
+        group.bench_function("u64_display", |b| b.iter(|| {
+            let mut buf = bytes::BytesMut::with_capacity(20);
+            let _ = fmt::Write::write_fmt(&mut Writer(&mut buf), format_args!("{}", black_box(&id)));
+            black_box(http::HeaderValue::from_maybe_shared(buf.freeze()).unwrap());
+        }));
+        group.bench_function("u64_stack", |b| b.iter(|| {
+            black_box(black_box(&id).to_header_value(20, 0));
+        }));
+        group.finish();
+    }
+
+    #[cfg(feature = "uuid")]
+    {
+        use tower_http_req_id::{Uuid, UuidGenerator};
+
+        let v4 = UuidGenerator::new_v4();
+        bench_service::<_, Uuid>(c, "uuid_v4", v4);
+        bench_format::<_, Uuid>(c, "uuid", v4);
+
+        let id = v4.gen();
+        let mut group = c.benchmark_group("header_value");
+        group.bench_function("uuid_display", |b| b.iter(|| {
+            let mut buf = bytes::BytesMut::with_capacity(IdGen::<Uuid>::len_hint(&v4));
+            let _ = fmt::Write::write_fmt(&mut Writer(&mut buf), format_args!("{}", black_box(&id)));
+            black_box(http::HeaderValue::from_maybe_shared(buf.freeze()).unwrap());
+        }));
+        let header_value = IdGen::<Uuid>::header_value_fn(&v4).unwrap();
+        group.bench_function("uuid_generator", |b| b.iter(|| {
+            black_box(header_value(black_box(&id)).unwrap());
+        }));
+        group.finish();
+
+        let mut group = c.benchmark_group("construct");
+        group.bench_function("uuid_new", |b| b.iter(|| black_box(UuidGenerator::new())));
+        group.bench_function("uuid_new_uncached", |b| b.iter(|| black_box(UuidGenerator::new_uncached())));
+        group.finish();
+
+        let v1 = UuidGenerator::new();
+        if v1.is_v1() {
+            bench_service::<_, Uuid>(c, "uuid_v1", v1);
+        }
+    }
+}
+
+criterion_group!(benches, generators);
+criterion_main!(benches);
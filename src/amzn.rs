@@ -0,0 +1,137 @@
+use core::{fmt, str};
+
+use crate::clock::Clock;
+use crate::utils::{fill_random, parse_hex};
+
+///Header name for AWS X-Ray trace header.
+pub const AMZN_TRACE_ID_HEADER_NAME: &str = "x-amzn-trace-id";
+
+//Root's identifier is 96bit
+const ID_MASK: u128 = (1 << 96) - 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+///ID in AWS X-Ray trace header format: `Root=1-{time}-{id}`
+///
+///Its root (e.g. `1-5759e988-bd862e3fe1be46a994272793`) is request's ID, consisting of UNIX time in seconds as 8 hex digits and 96bit identifier as 24 hex digits.
+///
+///Header's other fields (e.g. `Parent`, `Sampled` or `Self`, added by load balancer) are ignored when parsing, hence only root is echoed in response.
+pub struct AmznTraceId {
+    time: u32,
+    id: u128,
+}
+
+impl AmznTraceId {
+    #[inline(always)]
+    ///Creates new instance, using only lower 96 bits of `id`.
+    pub const fn new(time: u32, id: u128) -> Self {
+        Self {
+            time,
+            id: id & ID_MASK,
+        }
+    }
+
+    #[inline(always)]
+    ///Returns UNIX time in seconds, when trace is started.
+    pub const fn time(&self) -> u32 {
+        self.time
+    }
+
+    #[inline(always)]
+    ///Returns 96bit identifier of trace.
+    pub const fn id(&self) -> u128 {
+        self.id
+    }
+
+    #[inline]
+    ///Returns root of trace as `1-{time}-{id}`, which is value of header's `Root` field.
+    pub fn root(&self) -> alloc::string::String {
+        alloc::format!("1-{:08x}-{:024x}", self.time, self.id)
+    }
+}
+
+fn parse_root(root: &str) -> Option<AmznTraceId> {
+    let mut parts = root.split('-');
+    match parts.next() {
+        Some("1") => (),
+        _ => return None,
+    }
+    let time = parse_hex(parts.next()?, 8)? as u32;
+    let id = parse_hex(parts.next()?, 24)?;
+
+    match parts.next() {
+        Some(_) => None,
+        None => Some(AmznTraceId::new(time, id)),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Invalid AWS X-Ray trace header.
+pub struct InvalidAmznTraceId;
+
+impl str::FromStr for AmznTraceId {
+    type Err = InvalidAmznTraceId;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        for field in text.split(';') {
+            if let Some(root) = field.trim().strip_prefix("Root=") {
+                return parse_root(root).ok_or(InvalidAmznTraceId);
+            }
+        }
+
+        Err(InvalidAmznTraceId)
+    }
+}
+
+impl fmt::Display for AmznTraceId {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "Root=1-{:08x}-{:024x}", self.time, self.id)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Generator of AWS X-Ray trace ID with random identifier, started at time of generation.
+pub struct AmznTraceIdGenerator<C> {
+    clock: C,
+}
+
+impl AmznTraceIdGenerator<crate::clock::SystemClock> {
+    #[inline(always)]
+    ///Creates new instance, using system clock.
+    pub const fn new() -> Self {
+        Self::with_clock(crate::clock::SystemClock)
+    }
+}
+
+impl<C: Clock> AmznTraceIdGenerator<C> {
+    #[inline(always)]
+    ///Creates new instance, using specified clock.
+    pub const fn with_clock(clock: C) -> Self {
+        Self {
+            clock,
+        }
+    }
+}
+
+impl<C: Clock> super::IdGen<AmznTraceId> for AmznTraceIdGenerator<C> {
+    fn gen(&self) -> AmznTraceId {
+        let mut random = [0u8; 16];
+        fill_random(&mut random[..12]);
+
+        AmznTraceId::new(self.clock.unix_time().as_secs() as u32, u128::from_le_bytes(random))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        //5 + 1 + 1 + 8 + 1 + 24
+        40
+    }
+}
+
+impl super::GenerateRequestIdLayer<AmznTraceIdGenerator<crate::clock::SystemClock>, AmznTraceId> {
+    #[inline]
+    ///Creates layer, which reads and writes ID using `x-amzn-trace-id` header.
+    pub fn amzn_trace_id() -> Self {
+        Self::new(AmznTraceIdGenerator::new()).header_name(http::HeaderName::from_static(AMZN_TRACE_ID_HEADER_NAME))
+    }
+}
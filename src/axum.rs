@@ -0,0 +1,52 @@
+use core::fmt;
+use core::pin::Pin;
+use core::future::{Future, ready};
+use alloc::boxed::Box;
+
+use axum_core::extract::FromRequestParts;
+use axum_core::response::{IntoResponse, Response};
+use http::request::Parts;
+
+use crate::RequestId;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Rejection of `RequestId` extractor, when request has no ID.
+///
+///It is converted into `500 Internal Server Error`, as it indicates that middleware is not configured.
+pub struct MissingRequestId;
+
+impl fmt::Display for MissingRequestId {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("Request ID is missing")
+    }
+}
+
+impl IntoResponse for MissingRequestId {
+    #[inline]
+    fn into_response(self) -> Response {
+        (http::StatusCode::INTERNAL_SERVER_ERROR, "Request ID is missing").into_response()
+    }
+}
+
+///Extracts ID from request's extensions.
+///
+///ID stored as `RequestId<O>`, due to conflict with other value of type `O`, takes precedence.
+impl<S: Send + Sync, O: Clone + Send + Sync + 'static> FromRequestParts<S> for RequestId<O> {
+    type Rejection = MissingRequestId;
+
+    fn from_request_parts<'a, 'b, 'fut>(parts: &'a mut Parts, _: &'b S) -> Pin<Box<dyn Future<Output = Result<Self, Self::Rejection>> + Send + 'fut>>
+    where
+        'a: 'fut,
+        'b: 'fut,
+        Self: 'fut,
+    {
+        let id = match parts.extensions.get::<RequestId<O>>() {
+            Some(id) => Ok(id.clone()),
+            None => match parts.extensions.get::<O>() {
+                Some(id) => Ok(RequestId(id.clone())),
+                None => Err(MissingRequestId),
+            },
+        };
+        Box::pin(ready(id))
+    }
+}
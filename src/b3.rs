@@ -0,0 +1,175 @@
+use core::{fmt, str};
+
+use crate::utils::{fill_random, parse_hex};
+
+///Header name for B3 single header.
+pub const B3_HEADER_NAME: &str = "b3";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+///B3 sampling state.
+pub enum Sampling {
+    ///`0`
+    Deny,
+    ///`1`
+    Accept,
+    ///`d`
+    Debug,
+}
+
+impl Sampling {
+    #[inline(always)]
+    const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Deny => "0",
+            Self::Accept => "1",
+            Self::Debug => "d",
+        }
+    }
+
+    #[inline(always)]
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "0" => Some(Self::Deny),
+            "1" => Some(Self::Accept),
+            "d" => Some(Self::Debug),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+///ID in B3 single header format: `{traceid}-{spanid}[-{sampled}[-{parentspanid}]]`
+///
+///Its trace ID is request's ID, while whole value is echoed in response.
+pub struct B3 {
+    trace_id: u128,
+    //Whether trace_id is 64bit
+    is_short: bool,
+    span_id: u64,
+    sampling: Option<Sampling>,
+    parent_span_id: Option<u64>,
+}
+
+impl B3 {
+    #[inline(always)]
+    ///Creates new instance with 128bit trace ID.
+    pub const fn new(trace_id: u128, span_id: u64) -> Self {
+        Self {
+            trace_id,
+            is_short: false,
+            span_id,
+            sampling: None,
+            parent_span_id: None,
+        }
+    }
+
+    #[inline(always)]
+    ///Returns trace ID, which is request's ID.
+    pub const fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    #[inline(always)]
+    ///Returns span ID.
+    pub const fn span_id(&self) -> u64 {
+        self.span_id
+    }
+
+    #[inline(always)]
+    ///Returns sampling state, if specified.
+    pub const fn sampling(&self) -> Option<Sampling> {
+        self.sampling
+    }
+
+    #[inline(always)]
+    ///Returns parent span ID, if specified.
+    pub const fn parent_span_id(&self) -> Option<u64> {
+        self.parent_span_id
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Invalid B3 header.
+pub struct InvalidB3;
+
+impl str::FromStr for B3 {
+    type Err = InvalidB3;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = text.split('-');
+
+        let trace_id = parts.next().ok_or(InvalidB3)?;
+        let (trace_id, is_short) = match trace_id.len() {
+            16 => (parse_hex(trace_id, 16).ok_or(InvalidB3)?, true),
+            _ => (parse_hex(trace_id, 32).ok_or(InvalidB3)?, false),
+        };
+        let span_id = parts.next().and_then(|span_id| parse_hex(span_id, 16)).ok_or(InvalidB3)? as u64;
+        let sampling = match parts.next() {
+            Some(sampling) => Some(Sampling::parse(sampling).ok_or(InvalidB3)?),
+            None => None,
+        };
+        let parent_span_id = match parts.next() {
+            Some(parent_span_id) => Some(parse_hex(parent_span_id, 16).ok_or(InvalidB3)? as u64),
+            None => None,
+        };
+
+        match parts.next() {
+            Some(_) => Err(InvalidB3),
+            None => Ok(Self {
+                trace_id,
+                is_short,
+                span_id,
+                sampling,
+                parent_span_id,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for B3 {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.is_short {
+            true => write!(fmt, "{:016x}-{:016x}", self.trace_id, self.span_id)?,
+            false => write!(fmt, "{:032x}-{:016x}", self.trace_id, self.span_id)?,
+        }
+        if let Some(sampling) = self.sampling {
+            fmt.write_str("-")?;
+            fmt.write_str(sampling.as_str())?;
+            if let Some(parent_span_id) = self.parent_span_id {
+                write!(fmt, "-{:016x}", parent_span_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Generator of random B3 value with 128bit trace ID.
+pub struct B3Generator;
+
+impl super::IdGen<B3> for B3Generator {
+    fn gen(&self) -> B3 {
+        let mut random = [0u8; 24];
+        fill_random(&mut random);
+
+        let mut trace_id = [0u8; 16];
+        trace_id.copy_from_slice(&random[..16]);
+        let mut span_id = [0u8; 8];
+        span_id.copy_from_slice(&random[16..]);
+        B3::new(u128::from_ne_bytes(trace_id), u64::from_ne_bytes(span_id))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        //32 + 1 + 16 + 1 + 1 + 1 + 16
+        68
+    }
+}
+
+impl super::GenerateRequestIdLayer<B3Generator, B3> {
+    #[inline]
+    ///Creates layer, which reads and writes ID using `b3` header.
+    pub fn b3() -> Self {
+        Self::new(B3Generator).header_name(http::HeaderName::from_static(B3_HEADER_NAME))
+    }
+}
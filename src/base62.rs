@@ -0,0 +1,165 @@
+use core::{fmt, ops, str};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const ALPHABET: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+//Returns number of base62 digits to fit `len` bytes: ceil(len * 8 / log2(62))
+pub(crate) const fn encoded_len(len: usize) -> usize {
+    const LOG2_62: u64 = 5_954_196_310;
+    const SCALE: u64 = 1_000_000_000;
+
+    ((len as u64 * 8 * SCALE).div_ceil(LOG2_62)) as usize
+}
+
+//Encodes big-endian number into fixed width base62 string.
+pub(crate) fn encode(mut num: Vec<u8>, out: &mut String) {
+    let len = encoded_len(num.len());
+    let mut digits = Vec::with_capacity(len);
+
+    while digits.len() < len {
+        let mut rem = 0u32;
+        for byte in num.iter_mut() {
+            let acc = (rem << 8) | *byte as u32;
+            *byte = (acc / 62) as u8;
+            rem = acc % 62;
+        }
+        digits.push(ALPHABET[rem as usize]);
+    }
+
+    out.extend(digits.iter().rev().map(|digit| *digit as char));
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+///Base62 encoded token, consisting only of ASCII alphanumeric characters.
+pub struct Base62(pub(crate) String);
+
+impl Base62 {
+    #[inline(always)]
+    ///Returns underlying string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    #[inline(always)]
+    ///Returns underlying string.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl ops::Deref for Base62 {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for Base62 {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Invalid base62 token.
+pub struct InvalidBase62;
+
+impl str::FromStr for Base62 {
+    type Err = InvalidBase62;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+            true => Ok(Self(text.into())),
+            false => Err(InvalidBase62),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+///Generator of random base62 tokens.
+///
+///Token encodes specified number of random bytes, and its length is always the same.
+pub struct Base62Generator {
+    len: usize,
+}
+
+impl Base62Generator {
+    #[inline(always)]
+    ///Creates new instance, generating `len` random bytes per token.
+    pub const fn new(len: usize) -> Self {
+        Self {
+            len,
+        }
+    }
+
+    #[inline(always)]
+    ///Returns length of generated token.
+    pub const fn token_len(&self) -> usize {
+        encoded_len(self.len)
+    }
+
+    ///Generates token.
+    pub fn gen(&self) -> String {
+        let mut random = alloc::vec![0u8; self.len];
+        crate::utils::fill_random(&mut random);
+
+        let mut result = String::with_capacity(self.token_len());
+        encode(random, &mut result);
+        result
+    }
+}
+
+impl Default for Base62Generator {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl super::IdGen<String> for Base62Generator {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        Self::gen(self)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.token_len()
+    }
+}
+
+impl super::IdGen<Base62> for Base62Generator {
+    #[inline(always)]
+    fn gen(&self) -> Base62 {
+        Base62(Self::gen(self))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.token_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_encode_with_fixed_width() {
+        let mut out = String::new();
+        encode(alloc::vec![0, 0], &mut out);
+        assert_eq!(out, "000");
+
+        out.clear();
+        encode(alloc::vec![0xff, 0xff], &mut out);
+        //65535 = 17 * 62^2 + 3 * 62 + 1
+        assert_eq!(out, "H31");
+
+        assert_eq!(encoded_len(16), 22);
+        assert_eq!(encoded_len(32), 43);
+    }
+}
@@ -0,0 +1,126 @@
+use alloc::string::String;
+use std::sync::OnceLock;
+
+use crate::{IdGen, RequestRef};
+use crate::base62::{encode, encoded_len};
+
+//Number of random bytes in boot ID.
+const BOOT_BYTES: usize = 8;
+
+static BOOT_ID: OnceLock<String> = OnceLock::new();
+
+//Returns boot ID stored within `cell`, generating it on first access.
+fn boot_id(cell: &'static OnceLock<String>) -> &'static str {
+    cell.get_or_init(|| {
+        let mut bytes = [0u8; BOOT_BYTES];
+        crate::utils::fill_random(&mut bytes);
+
+        let mut result = String::with_capacity(encoded_len(BOOT_BYTES));
+        encode(bytes.to_vec(), &mut result);
+        result
+    })
+}
+
+#[derive(Clone, Copy, Debug)]
+///Generator, which prefixes `String` ID of inner generator with boot ID: `{boot_id}-{id}`.
+///
+///Boot ID is random base62 token, generated once per process, hence IDs are attributable to process's lifetime
+///and IDs generated before and after restart never collide.
+pub struct BootScopedGenerator<G> {
+    inner: G,
+    boot_id: &'static str,
+}
+
+impl<G> BootScopedGenerator<G> {
+    #[inline]
+    ///Creates new instance.
+    pub fn new(inner: G) -> Self {
+        Self::with_cell(inner, &BOOT_ID)
+    }
+
+    #[inline(always)]
+    fn with_cell(inner: G, cell: &'static OnceLock<String>) -> Self {
+        Self {
+            inner,
+            boot_id: boot_id(cell),
+        }
+    }
+
+    #[inline(always)]
+    ///Returns boot ID of current process.
+    pub fn boot_id(&self) -> &'static str {
+        self.boot_id
+    }
+
+    fn tag(&self, id: &str) -> String {
+        let mut result = String::with_capacity(self.boot_id.len() + 1 + id.len());
+        result.push_str(self.boot_id);
+        result.push('-');
+        result.push_str(id);
+        result
+    }
+}
+
+impl<G: Default> Default for BootScopedGenerator<G> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new(G::default())
+    }
+}
+
+impl<G: IdGen<String>> IdGen<String> for BootScopedGenerator<G> {
+    #[inline]
+    fn gen(&self) -> String {
+        self.tag(&self.inner.gen())
+    }
+
+    #[inline]
+    fn gen_for(&self, req: RequestRef<'_>) -> String {
+        self.tag(&self.inner.gen_for(req))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.boot_id.len() + 1 + self.inner.len_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct TestGenerator;
+
+    impl IdGen<String> for TestGenerator {
+        fn gen(&self) -> String {
+            "id".into()
+        }
+    }
+
+    #[test]
+    fn verify_boot_id_is_stable_within_process() {
+        let first = BootScopedGenerator::new(TestGenerator);
+        let second = BootScopedGenerator::new(TestGenerator);
+
+        assert_eq!(first.boot_id().len(), encoded_len(BOOT_BYTES));
+        assert_eq!(first.boot_id(), second.boot_id());
+        assert_eq!(first.gen(), second.gen());
+        assert_eq!(first.gen(), alloc::format!("{}-id", first.boot_id()));
+    }
+
+    #[test]
+    fn verify_boot_id_differs_between_processes() {
+        static FIRST_PROCESS: OnceLock<String> = OnceLock::new();
+        static SECOND_PROCESS: OnceLock<String> = OnceLock::new();
+
+        let first = BootScopedGenerator::with_cell(TestGenerator, &FIRST_PROCESS);
+        let second = BootScopedGenerator::with_cell(TestGenerator, &SECOND_PROCESS);
+
+        assert_ne!(first.boot_id(), second.boot_id());
+        assert_ne!(first.gen(), second.gen());
+        assert_eq!(first.boot_id(), BootScopedGenerator::with_cell(TestGenerator, &FIRST_PROCESS).boot_id());
+        assert!(first.gen().starts_with(first.boot_id()));
+        assert!(second.gen().starts_with(second.boot_id()));
+    }
+}
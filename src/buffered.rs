@@ -0,0 +1,405 @@
+use core::{fmt, task};
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{self, AtomicUsize, Ordering};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use crate::{IdGen, RequestRef};
+
+struct Slot<O> {
+    //Position, at which slot can be written (if equal to slot's position) or read (if one past it)
+    stamp: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<O>>,
+}
+
+//Bounded lock-free MPMC ring buffer.
+//
+//Positions of `head` and `tail` consist of index within buffer and lap, which is multiple of `one_lap`.
+//Each slot's stamp tells whether slot is ready for writing or reading at given position,
+//so producers and consumers only contend on single atomic each, and never wait for each other unless buffer is full or empty.
+struct Ring<O> {
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    one_lap: usize,
+    slots: Box<[Slot<O>]>,
+}
+
+//Values are only moved in and out of slots, exclusive access to which is granted by stamps.
+unsafe impl<O: Send> Send for Ring<O> {}
+unsafe impl<O: Send> Sync for Ring<O> {}
+
+impl<O> Ring<O> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            one_lap: (capacity + 1).next_power_of_two(),
+            slots: (0..capacity).map(|idx| Slot {
+                stamp: AtomicUsize::new(idx),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            }).collect(),
+        }
+    }
+
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    #[inline(always)]
+    //Returns position, following `pos`
+    fn next(&self, pos: usize) -> usize {
+        let index = pos & (self.one_lap - 1);
+        match index + 1 < self.capacity() {
+            true => pos + 1,
+            false => (pos & !(self.one_lap - 1)).wrapping_add(self.one_lap),
+        }
+    }
+
+    fn push(&self, value: O) -> Result<(), O> {
+        if self.slots.is_empty() {
+            return Err(value);
+        }
+
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[tail & (self.one_lap - 1)];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if tail == stamp {
+                match self.tail.compare_exchange_weak(tail, self.next(tail), Ordering::SeqCst, Ordering::Relaxed) {
+                    Ok(_) => {
+                        unsafe {
+                            slot.value.get().write(MaybeUninit::new(value));
+                        }
+                        slot.stamp.store(tail + 1, Ordering::Release);
+                        break Ok(());
+                    },
+                    Err(actual) => tail = actual,
+                }
+            } else if stamp.wrapping_add(self.one_lap) == tail + 1 {
+                //Slot still holds value from previous lap, so buffer is full unless it is being popped right now
+                atomic::fence(Ordering::SeqCst);
+                if self.head.load(Ordering::Relaxed).wrapping_add(self.one_lap) == tail {
+                    break Err(value);
+                }
+                tail = self.tail.load(Ordering::Relaxed);
+            } else {
+                core::hint::spin_loop();
+                tail = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn pop(&self) -> Option<O> {
+        if self.slots.is_empty() {
+            return None;
+        }
+
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[head & (self.one_lap - 1)];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if head + 1 == stamp {
+                match self.head.compare_exchange_weak(head, self.next(head), Ordering::SeqCst, Ordering::Relaxed) {
+                    Ok(_) => {
+                        let value = unsafe {
+                            slot.value.get().read().assume_init()
+                        };
+                        slot.stamp.store(head.wrapping_add(self.one_lap), Ordering::Release);
+                        break Some(value);
+                    },
+                    Err(actual) => head = actual,
+                }
+            } else if stamp == head {
+                //Slot is not written yet, so buffer is empty unless it is being pushed right now
+                atomic::fence(Ordering::SeqCst);
+                if self.tail.load(Ordering::Relaxed) == head {
+                    break None;
+                }
+                head = self.head.load(Ordering::Relaxed);
+            } else {
+                core::hint::spin_loop();
+                head = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        loop {
+            let tail = self.tail.load(Ordering::SeqCst);
+            let head = self.head.load(Ordering::SeqCst);
+
+            //Retry until consistent snapshot of both positions is taken
+            if self.tail.load(Ordering::SeqCst) == tail {
+                let head_idx = head & (self.one_lap - 1);
+                let tail_idx = tail & (self.one_lap - 1);
+
+                break if head_idx < tail_idx {
+                    tail_idx - head_idx
+                } else if head_idx > tail_idx {
+                    self.capacity() - head_idx + tail_idx
+                } else if tail == head {
+                    0
+                } else {
+                    self.capacity()
+                };
+            }
+        }
+    }
+}
+
+impl<O> Drop for Ring<O> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {
+        }
+    }
+}
+
+///Generator, which serves IDs from bounded buffer, pre-filled ahead of time.
+///
+///Buffer is shared between clones of generator, and it is filled via `refill` or `push`, usually from background task.
+///When buffer is empty, ID is generated by inner generator within request's processing.
+///
+///Buffer is lock-free ring, hence taking ID out of it never blocks, even while it is being refilled concurrently.
+pub struct BufferedGenerator<G, O> {
+    inner: G,
+    capacity: usize,
+    buffer: Arc<Ring<O>>,
+}
+
+impl<G, O> BufferedGenerator<G, O> {
+    #[inline]
+    ///Creates new instance with empty buffer, holding at most `capacity` IDs.
+    pub fn new(inner: G, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            buffer: Arc::new(Ring::new(capacity)),
+        }
+    }
+
+    #[inline(always)]
+    ///Returns maximum number of buffered IDs.
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    ///Returns number of currently buffered IDs.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    #[inline]
+    ///Returns whether buffer is empty, meaning next ID is generated by inner generator.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    ///Adds pre-generated `id` to the buffer.
+    ///
+    ///Returns `Err` with `id` if buffer is full.
+    pub fn push(&self, id: O) -> Result<(), O> {
+        self.buffer.push(id)
+    }
+
+    #[inline]
+    fn pop(&self) -> Option<O> {
+        self.buffer.pop()
+    }
+}
+
+impl<G: IdGen<O>, O> BufferedGenerator<G, O> {
+    ///Fills buffer up to its capacity using inner generator.
+    ///
+    ///Returns number of added IDs.
+    pub fn refill(&self) -> usize {
+        let mut added = 0;
+        while self.len() < self.capacity {
+            match self.push(self.inner.gen()) {
+                Ok(()) => added += 1,
+                //Filled concurrently
+                Err(_) => break,
+            }
+        }
+        added
+    }
+}
+
+impl<G: Clone, O> Clone for BufferedGenerator<G, O> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            capacity: self.capacity,
+            buffer: self.buffer.clone(),
+        }
+    }
+}
+
+impl<G: fmt::Debug, O> fmt::Debug for BufferedGenerator<G, O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("BufferedGenerator").field("inner", &self.inner).field("capacity", &self.capacity).field("len", &self.len()).finish()
+    }
+}
+
+impl<G: IdGen<O>, O> IdGen<O> for BufferedGenerator<G, O> {
+    #[inline]
+    fn gen(&self) -> O {
+        match self.pop() {
+            Some(id) => id,
+            None => self.inner.gen(),
+        }
+    }
+
+    #[inline]
+    fn gen_for(&self, req: RequestRef<'_>) -> O {
+        match self.pop() {
+            Some(id) => id,
+            None => self.inner.gen_for(req),
+        }
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.inner.len_hint()
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &O) -> Option<http::HeaderValue> {
+        self.inner.header_value(id)
+    }
+
+    #[inline(always)]
+    fn header_value_fn(&self) -> Option<fn(&O) -> Option<http::HeaderValue>> {
+        self.inner.header_value_fn()
+    }
+
+    #[inline(always)]
+    fn write_id(&self) -> Option<fn(&O, &mut dyn fmt::Write) -> fmt::Result> {
+        self.inner.write_id()
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        self.inner.poll_ready(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    #[derive(Clone, Default)]
+    struct CounterGenerator(Arc<AtomicU64>);
+
+    impl IdGen<u64> for CounterGenerator {
+        fn gen(&self) -> u64 {
+            self.0.fetch_add(1, Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn should_serve_buffered_ids() {
+        let gen = BufferedGenerator::new(CounterGenerator::default(), 3);
+        assert!(gen.is_empty());
+        assert_eq!(gen.refill(), 3);
+        assert_eq!(gen.refill(), 0);
+        assert_eq!(gen.len(), 3);
+
+        let shared = gen.clone();
+        assert_eq!(shared.gen(), 0);
+        assert_eq!(gen.gen(), 1);
+        assert_eq!(gen.len(), 1);
+        assert_eq!(gen.push(42), Ok(()));
+        assert_eq!(gen.push(43), Ok(()));
+        assert_eq!(gen.push(44), Err(44));
+        assert_eq!(gen.gen(), 2);
+        assert_eq!(gen.gen(), 42);
+        assert_eq!(gen.gen(), 43);
+    }
+
+    #[test]
+    fn should_fall_back_to_inner_generator() {
+        let gen = BufferedGenerator::new(CounterGenerator::default(), 2);
+        assert_eq!(gen.gen(), 0);
+        assert_eq!(gen.gen(), 1);
+
+        assert_eq!(gen.refill(), 2);
+        assert_eq!(gen.gen(), 2);
+        assert_eq!(gen.gen(), 3);
+        assert!(gen.is_empty());
+        assert_eq!(gen.gen(), 4);
+        assert_eq!(gen.inner.0.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn should_serve_each_buffered_id_once_across_threads() {
+        const THREADS: u64 = 4;
+        const IDS: u64 = 1_000;
+
+        let gen = BufferedGenerator::new(CounterGenerator::default(), 7);
+        let producer = {
+            let gen = gen.clone();
+            std::thread::spawn(move || {
+                for id in 0..THREADS * IDS {
+                    let mut id = IDS * THREADS + id;
+                    while let Err(rejected) = gen.push(id) {
+                        id = rejected;
+                        std::thread::yield_now();
+                    }
+                }
+            })
+        };
+
+        let consumers = (0..THREADS).map(|_| {
+            let gen = gen.clone();
+            std::thread::spawn(move || (0..IDS).map(|_| gen.gen()).collect::<std::vec::Vec<_>>())
+        }).collect::<std::vec::Vec<_>>();
+
+        let mut ids = consumers.into_iter().flat_map(|consumer| consumer.join().expect("consumer to succeed")).collect::<std::vec::Vec<_>>();
+        //Take out the rest, so that producer is not stuck on full buffer
+        while !producer.is_finished() || !gen.is_empty() {
+            match gen.pop() {
+                Some(id) => ids.push(id),
+                None => std::thread::yield_now(),
+            }
+        }
+        producer.join().expect("producer to succeed");
+        assert!(gen.is_empty());
+
+        //Every pushed ID is served exactly once, and every ID not taken from buffer comes from inner generator
+        ids.sort_unstable();
+        let generated = gen.inner.0.load(Ordering::Relaxed);
+        let expected = (0..generated).chain(IDS * THREADS..2 * IDS * THREADS).collect::<std::vec::Vec<_>>();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn should_drop_buffered_ids() {
+        let id = Arc::new(());
+        let gen = BufferedGenerator::<CounterGenerator, _>::new(CounterGenerator::default(), 2);
+        assert!(gen.push(id.clone()).is_ok());
+        assert!(gen.push(id.clone()).is_ok());
+        assert!(gen.push(id.clone()).is_err());
+        assert_eq!(Arc::strong_count(&id), 3);
+
+        drop(gen);
+        assert_eq!(Arc::strong_count(&id), 1);
+    }
+
+    #[test]
+    fn should_not_buffer_with_zero_capacity() {
+        let gen = BufferedGenerator::new(CounterGenerator::default(), 0);
+        assert_eq!(gen.push(42), Err(42));
+        assert_eq!(gen.refill(), 0);
+        assert!(gen.is_empty());
+        assert_eq!(gen.gen(), 0);
+    }
+}
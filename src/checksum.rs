@@ -0,0 +1,100 @@
+use core::task;
+use alloc::string::String;
+
+use crate::{IdGen, IdScheme, RequestRef};
+
+//Symbols of checksum, whose position is their value.
+const ALPHABET: &[u8; 37] = b"0123456789abcdefghijklmnopqrstuvwxyz-";
+//Prime modulus, equal to number of symbols.
+const MODULUS: u32 = ALPHABET.len() as u32;
+
+#[inline]
+fn symbol_value(byte: u8) -> u32 {
+    match byte {
+        b'0'..=b'9' => (byte - b'0') as u32,
+        b'a'..=b'z' => (byte - b'a') as u32 + 10,
+        b'-' => 36,
+        //Characters outside of alphabet are not guaranteed to be distinct
+        byte => byte as u32 % MODULUS,
+    }
+}
+
+//Sum of symbol values, weighted by powers of 2 according to distance from the end (ISO 7064 MOD 37-2 style).
+//
+//As modulus is prime and 2 is its primitive root, every weight is non-zero, which guarantees detection of single substituted symbol.
+//Weights of adjacent positions differ by factor of 2, which guarantees detection of swapped adjacent symbols.
+fn weighted_sum(id: &[u8]) -> u32 {
+    id.iter().fold(0, |sum, byte| (sum * 2 + symbol_value(*byte)) % MODULUS)
+}
+
+//Returns check symbol, which makes weighted sum of `id` followed by it zero.
+fn checksum(id: &[u8]) -> u8 {
+    let sum = weighted_sum(id) * 2 % MODULUS;
+    ALPHABET[((MODULUS - sum) % MODULUS) as usize]
+}
+
+///Checks whether `id` ends with valid checksum character, produced by `ChecksummedGenerator`.
+pub fn verify_checksum(id: &str) -> bool {
+    id.len() > 1 && weighted_sum(id.as_bytes()) == 0
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Generator, which appends checksum character to `String` ID of inner generator.
+///
+///Checksum is single character out of `[0-9a-z-]`, computed as sum of ID's characters weighted by powers of 2, modulo 37.
+///This allows to detect truncation or corruption of ID, as it passes through other systems.
+///
+///When ID consists of lower case ASCII alphanumeric characters and `-` (e.g. UUID or hex token), any single substituted character and any swap of adjacent characters is detected.
+///Other characters are still covered, but substitution of one by another, whose code differs by multiple of 37, is not detected.
+///
+///It is also `IdScheme`, replacing client's ID with generated one, unless its checksum is valid, when used with `GenerateRequestIdLayer::with_scheme`.
+pub struct ChecksummedGenerator<G> {
+    inner: G,
+}
+
+impl<G> ChecksummedGenerator<G> {
+    #[inline(always)]
+    ///Creates new instance.
+    pub const fn new(inner: G) -> Self {
+        Self {
+            inner,
+        }
+    }
+}
+
+fn append_checksum(mut id: String) -> String {
+    let check = checksum(id.as_bytes());
+    id.push(check as char);
+    id
+}
+
+impl<G: IdGen<String>> IdGen<String> for ChecksummedGenerator<G> {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        append_checksum(self.inner.gen())
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> String {
+        append_checksum(self.inner.gen_for(req))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.inner.len_hint() + 1
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        self.inner.poll_ready(ctx)
+    }
+}
+
+impl<G: IdGen<String>> IdScheme for ChecksummedGenerator<G> {
+    type Id = String;
+
+    #[inline(always)]
+    fn validate(id: &String) -> bool {
+        verify_checksum(id)
+    }
+}
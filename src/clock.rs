@@ -0,0 +1,39 @@
+use core::time::Duration;
+
+///Source of current time, used by time based generators.
+///
+///It allows to substitute system time, e.g. in tests.
+pub trait Clock {
+    ///Returns time elapsed since UNIX epoch.
+    fn unix_time(&self) -> Duration;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    #[inline(always)]
+    fn unix_time(&self) -> Duration {
+        C::unix_time(self)
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for alloc::sync::Arc<C> {
+    #[inline(always)]
+    fn unix_time(&self) -> Duration {
+        C::unix_time(self)
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+///System clock.
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    #[inline]
+    fn unix_time(&self) -> Duration {
+        match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+            Ok(time) => time,
+            Err(_) => Duration::ZERO,
+        }
+    }
+}
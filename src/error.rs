@@ -0,0 +1,26 @@
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+///Error of fallible operations within this crate.
+pub enum RequestIdError {
+    ///Header name is not valid.
+    InvalidHeaderName,
+    ///ID's textual representation is not valid header value.
+    InvalidHeaderValue,
+    ///ID is required in request's extensions, but layer is configured not to insert it.
+    ExtensionDisabled,
+}
+
+impl fmt::Display for RequestIdError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeaderName => fmt.write_str("Invalid header name"),
+            Self::InvalidHeaderValue => fmt.write_str("ID is not valid header value"),
+            Self::ExtensionDisabled => fmt.write_str("ID is not inserted into request's extensions"),
+        }
+    }
+}
+
+impl core::error::Error for RequestIdError {
+}
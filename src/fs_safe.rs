@@ -0,0 +1,94 @@
+use core::task;
+use alloc::string::String;
+
+use crate::{IdGen, IdScheme, RequestRef};
+
+///Maximum length of file name on most filesystems.
+const MAX_LEN: usize = 255;
+
+#[inline(always)]
+fn is_fs_safe_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-' || byte == b'.'
+}
+
+///Checks whether `text` can be used both as header value and as file name.
+///
+///Such text consists only of ASCII alphanumeric characters, `_`, `-` and `.`, doesn't start with `.` or `-`, and fits into 255 bytes.
+///This excludes path separators, drive delimiters (`:`), and names like `..` or `-rf`.
+pub fn is_fs_safe(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    match bytes.first() {
+        Some(b'.') | Some(b'-') | None => false,
+        Some(_) => bytes.len() <= MAX_LEN && bytes.iter().all(|byte| is_fs_safe_byte(*byte)),
+    }
+}
+
+fn sanitize(id: String) -> String {
+    if is_fs_safe(&id) {
+        return id;
+    }
+
+    let mut result = String::with_capacity(core::cmp::min(id.len(), MAX_LEN));
+    for byte in id.bytes().take(MAX_LEN) {
+        match is_fs_safe_byte(byte) && !(result.is_empty() && (byte == b'.' || byte == b'-')) {
+            true => result.push(byte as char),
+            false => result.push('_'),
+        }
+    }
+    if result.is_empty() {
+        result.push('_');
+    }
+    result
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Generator of IDs, which are valid both as header values and as file names.
+///
+///ID of inner generator is used as it is when it satisfies `is_fs_safe`.
+///Otherwise every disallowed character is replaced with `_`, and ID is truncated to 255 bytes.
+///
+///It is also `IdScheme`, rejecting client's ID unless it satisfies `is_fs_safe`, when used with `GenerateRequestIdLayer::with_scheme`.
+pub struct FsSafeGenerator<G> {
+    inner: G,
+}
+
+impl<G> FsSafeGenerator<G> {
+    #[inline(always)]
+    ///Creates new instance.
+    pub const fn new(inner: G) -> Self {
+        Self {
+            inner,
+        }
+    }
+}
+
+impl<G: IdGen<String>> IdGen<String> for FsSafeGenerator<G> {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        sanitize(self.inner.gen())
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> String {
+        sanitize(self.inner.gen_for(req))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.inner.len_hint()
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        self.inner.poll_ready(ctx)
+    }
+}
+
+impl<G: IdGen<String>> IdScheme for FsSafeGenerator<G> {
+    type Id = String;
+
+    #[inline(always)]
+    fn validate(id: &String) -> bool {
+        is_fs_safe(id)
+    }
+}
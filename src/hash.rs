@@ -0,0 +1,71 @@
+use core::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::utils;
+
+struct HashWriter<'a>(&'a mut Sha256);
+
+impl fmt::Write for HashWriter<'_> {
+    #[inline(always)]
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.0.update(text.as_bytes());
+        Ok(())
+    }
+}
+
+fn hex_digest<T: fmt::Display>(salt: &[u8], id: &T) -> [u8; 64] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    let _ = fmt::Write::write_fmt(&mut HashWriter(&mut hasher), format_args!("{}", id));
+    let digest = hasher.finalize();
+
+    let mut hex = [0u8; 64];
+    utils::hex_encode(&digest, &mut hex);
+    hex
+}
+
+//Compares slices without short-circuiting on first mismatching byte, so that comparison time depends only on length.
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+
+    let diff = left.iter().zip(right.iter()).fold(0u8, |diff, (left, right)| diff | (left ^ right));
+    //Prevent compiler from turning fold into early return.
+    core::hint::black_box(diff) == 0
+}
+
+///Creates header value as lower case hex of `SHA-256(salt || id)`.
+pub(crate) fn header_value<T: fmt::Display>(salt: &[u8], id: &T) -> http::HeaderValue {
+    let hex = hex_digest(salt, id);
+    //Safety: only hex digits are written
+    unsafe {
+        http::HeaderValue::from_maybe_shared_unchecked(bytes::Bytes::copy_from_slice(&hex))
+    }
+}
+
+///Checks whether `value` is hash of `id`, as written by `GenerateRequestIdLayer::response_hash` with the same `salt`.
+///
+///Comparison is performed in constant time, regardless of how many leading characters of `value` match expected hash.
+///Hence it is not possible to forge hash character by character, measuring time of verification.
+///Only hash's length, which is always 64 characters, is revealed.
+///Note that comparison is case sensitive, as hash is always written in lower case.
+pub fn verify_response_hash<T: fmt::Display>(salt: &[u8], id: &T, value: &[u8]) -> bool {
+    constant_time_eq(&hex_digest(salt, id), value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_eq;
+
+    #[test]
+    fn verify_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"xbc"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert!(!constant_time_eq(b"ab", b"abc"));
+    }
+}
@@ -0,0 +1,126 @@
+use core::{fmt, str};
+use alloc::string::String;
+
+use crate::{utils, AlwaysValidHeader};
+
+#[inline(always)]
+fn hex_digit(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+///Raw `N` bytes ID, which textual representation is lower case hex string of `2N` characters.
+pub struct RawId<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> RawId<N> {
+    #[inline(always)]
+    ///Returns underlying bytes.
+    pub const fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> fmt::Display for RawId<N> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //Pad as whole, to respect formatter's width
+        if fmt.width().is_some() {
+            return fmt.pad(&alloc::format!("{:x}", self));
+        }
+        fmt::LowerHex::fmt(self, fmt)
+    }
+}
+
+impl<const N: usize> fmt::LowerHex for RawId<N> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = [0u8; 2];
+        for byte in self.0.iter() {
+            utils::hex_encode(core::slice::from_ref(byte), &mut buf);
+            //Safety: only hex digits are written
+            fmt.write_str(unsafe { str::from_utf8_unchecked(&buf) })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Invalid hex ID.
+pub struct InvalidHex;
+
+impl<const N: usize> str::FromStr for RawId<N> {
+    type Err = InvalidHex;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.as_bytes();
+        if text.len() != N * 2 {
+            return Err(InvalidHex);
+        }
+
+        let mut result = [0u8; N];
+        for (byte, digits) in result.iter_mut().zip(text.chunks_exact(2)) {
+            match (hex_digit(digits[0]), hex_digit(digits[1])) {
+                (Some(high), Some(low)) => *byte = (high << 4) | low,
+                _ => return Err(InvalidHex),
+            }
+        }
+        Ok(Self(result))
+    }
+}
+
+//Safety: only hex digits are written, and padding is done with zeroes.
+unsafe impl<const N: usize> AlwaysValidHeader for RawId<N> {
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Generator of `N` random bytes, encoded as lower case hex string of `2N` characters.
+///
+///Unlike `Base62Generator`, length is known at compile time, hence random bytes are stored on stack.
+pub struct FixedHexGenerator<const N: usize>;
+
+impl<const N: usize> FixedHexGenerator<N> {
+    #[inline(always)]
+    ///Creates new instance.
+    pub const fn new() -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    ///Returns length of generated token.
+    pub const fn token_len(&self) -> usize {
+        N * 2
+    }
+
+    ///Generates raw ID.
+    pub fn gen(&self) -> RawId<N> {
+        let mut random = [0u8; N];
+        utils::fill_random(&mut random);
+        RawId(random)
+    }
+}
+
+impl<const N: usize> super::IdGen<String> for FixedHexGenerator<N> {
+    fn gen(&self) -> String {
+        utils::hex_string(&Self::gen(self).0)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.token_len()
+    }
+}
+
+impl<const N: usize> super::IdGen<RawId<N>> for FixedHexGenerator<N> {
+    #[inline(always)]
+    fn gen(&self) -> RawId<N> {
+        Self::gen(self)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.token_len()
+    }
+}
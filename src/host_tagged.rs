@@ -0,0 +1,72 @@
+use alloc::string::String;
+
+use crate::{IdGen, RequestRef};
+
+#[derive(Clone, Copy, Debug)]
+///Generator, which prefixes `String` ID of inner generator with request's host: `{host}-{id}`.
+///
+///Host is taken from request's URI authority, or from `Host` header, without port.
+///It is lower cased, and any character other than ASCII alphanumeric, `.` and `-` is replaced with `_`.
+///
+///When request has no host, or ID is generated without request (e.g. pre-generated in `poll_ready`), `default` host is used.
+pub struct HostTaggedGenerator<G> {
+    inner: G,
+    default: &'static str,
+}
+
+impl<G> HostTaggedGenerator<G> {
+    #[inline(always)]
+    ///Creates new instance, using `default` as host of request without one.
+    pub const fn new(inner: G, default: &'static str) -> Self {
+        Self {
+            inner,
+            default,
+        }
+    }
+}
+
+fn host<'a>(req: &RequestRef<'a>) -> Option<&'a str> {
+    if let Some(authority) = req.uri().authority() {
+        return Some(authority.host());
+    }
+
+    let host = req.headers().get(http::header::HOST)?.to_str().ok()?;
+    match host.starts_with('[') {
+        //IPv6 literal
+        true => host.find(']').map(|end| &host[..=end]),
+        false => host.split(':').next(),
+    }
+}
+
+fn tag(host: &str, id: &str) -> String {
+    let mut result = String::with_capacity(host.len() + 1 + id.len());
+    for ch in host.chars() {
+        match ch.is_ascii_alphanumeric() || ch == '.' || ch == '-' {
+            true => result.push(ch.to_ascii_lowercase()),
+            false => result.push('_'),
+        }
+    }
+    result.push('-');
+    result.push_str(id);
+    result
+}
+
+impl<G: IdGen<String>> IdGen<String> for HostTaggedGenerator<G> {
+    #[inline]
+    fn gen(&self) -> String {
+        tag(self.default, &self.inner.gen())
+    }
+
+    fn gen_for(&self, req: RequestRef<'_>) -> String {
+        let host = match host(&req) {
+            Some(host) if !host.is_empty() => host,
+            _ => self.default,
+        };
+        tag(host, &self.inner.gen_for(req))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.default.len() + 1 + self.inner.len_hint()
+    }
+}
@@ -0,0 +1,117 @@
+use core::{fmt, task};
+use core::str::FromStr;
+use alloc::string::{String, ToString};
+
+use crate::{IdGen, RequestRef};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+///ID, which is either parsed from client as `I`, or generated by middleware as `G`.
+///
+///This allows to accept arbitrary ID from client, while generating typed one, and still tell which one is used.
+///Both representations can be unified into common type `T` via `unify`, as long as both are `Into<T>`.
+///For most common case of collapsing ID into `String`, there is `From<HybridId<I, G>>` for `String`.
+///
+///Use `HybridGenerator` to produce `Generated` variant out of generator of `G`.
+pub enum HybridId<I, G> {
+    ///ID received from client.
+    Inbound(I),
+    ///ID generated by middleware.
+    Generated(G),
+}
+
+impl<I, G> HybridId<I, G> {
+    #[inline(always)]
+    ///Returns whether ID is received from client.
+    pub const fn is_inbound(&self) -> bool {
+        matches!(self, Self::Inbound(_))
+    }
+
+    #[inline(always)]
+    ///Returns whether ID is generated by middleware.
+    pub const fn is_generated(&self) -> bool {
+        matches!(self, Self::Generated(_))
+    }
+
+    #[inline]
+    ///Converts either representation into common type `T`.
+    pub fn unify<T>(self) -> T where I: Into<T>, G: Into<T> {
+        match self {
+            Self::Inbound(id) => id.into(),
+            Self::Generated(id) => id.into(),
+        }
+    }
+}
+
+impl<I: fmt::Display, G: fmt::Display> fmt::Display for HybridId<I, G> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inbound(id) => fmt::Display::fmt(id, fmt),
+            Self::Generated(id) => fmt::Display::fmt(id, fmt),
+        }
+    }
+}
+
+impl<I: FromStr, G> FromStr for HybridId<I, G> {
+    type Err = I::Err;
+
+    #[inline(always)]
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        I::from_str(text).map(Self::Inbound)
+    }
+}
+
+impl<I: fmt::Display, G: fmt::Display> From<HybridId<I, G>> for String {
+    #[inline]
+    fn from(id: HybridId<I, G>) -> Self {
+        id.to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Generator of `HybridId`, which wraps generator of `G`.
+///
+///ID parsed from client is always `HybridId::Inbound`, while generated one is `HybridId::Generated`.
+pub struct HybridGenerator<T> {
+    inner: T,
+}
+
+impl<T> HybridGenerator<T> {
+    #[inline(always)]
+    ///Creates new instance.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+        }
+    }
+}
+
+impl<I, G, T: IdGen<G>> IdGen<HybridId<I, G>> for HybridGenerator<T> {
+    #[inline(always)]
+    fn gen(&self) -> HybridId<I, G> {
+        HybridId::Generated(self.inner.gen())
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> HybridId<I, G> {
+        HybridId::Generated(self.inner.gen_for(req))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.inner.len_hint()
+    }
+
+    #[inline]
+    fn header_value(&self, id: &HybridId<I, G>) -> Option<http::HeaderValue> {
+        match id {
+            HybridId::Generated(id) => self.inner.header_value(id),
+            HybridId::Inbound(_) => None,
+        }
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        self.inner.poll_ready(ctx)
+    }
+}
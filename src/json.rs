@@ -0,0 +1,375 @@
+use core::{fmt, mem, task};
+use core::pin::Pin;
+use core::future::Future;
+use core::marker::PhantomData;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use bytes::{Buf, Bytes};
+use http::{Request, Response};
+use tower_layer::Layer;
+use tower_service::Service;
+
+///Default name of JSON field to write ID into.
+pub const JSON_FIELD: &str = "request_id";
+
+///Serializes `body` and injects `id` into it as string field `request_id`.
+///
+///Field is only added when `body` is serialized as JSON object, overwriting existing field with the same name.
+///Any other JSON value (array, string, etc) is returned as it is, because there is no place for extra field in it.
+pub fn inject_request_id<T: serde::Serialize + ?Sized, O: fmt::Display + ?Sized>(body: &T, id: &O) -> Result<serde_json::Value, serde_json::Error> {
+    let mut body = serde_json::to_value(body)?;
+    if let serde_json::Value::Object(object) = &mut body {
+        object.insert(JSON_FIELD.to_string(), serde_json::Value::String(id.to_string()));
+    }
+    Ok(body)
+}
+
+//Summary of JSON object's top-level keys.
+struct ObjectKeys {
+    is_empty: bool,
+    has_field: bool,
+}
+
+//Validates JSON object without building it, looking for `field` among its keys.
+struct ObjectKeysVisitor<'a> {
+    field: &'a str,
+}
+
+impl<'de> serde::de::Visitor<'de> for ObjectKeysVisitor<'_> {
+    type Value = ObjectKeys;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("JSON object")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut keys = ObjectKeys {
+            is_empty: true,
+            has_field: false,
+        };
+        while let Some(key) = map.next_key::<String>()? {
+            keys.is_empty = false;
+            keys.has_field |= key == self.field;
+            map.next_value::<serde::de::IgnoredAny>()?;
+        }
+        Ok(keys)
+    }
+}
+
+fn object_keys(body: &[u8], field: &str) -> Result<ObjectKeys, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let keys = serde::Deserializer::deserialize_map(&mut deserializer, ObjectKeysVisitor { field })?;
+    deserializer.end()?;
+    Ok(keys)
+}
+
+//Splices field right after object's opening brace, leaving the rest of body intact.
+fn inject_into_bytes(body: Vec<u8>, field: &str, id: String) -> Bytes {
+    let keys = match object_keys(&body, field) {
+        Ok(keys) if !keys.has_field => keys,
+        _ => return body.into(),
+    };
+    //Valid JSON object starts with brace, optionally preceded by whitespace
+    let start = match body.iter().position(|byte| *byte == b'{') {
+        Some(brace) => brace + 1,
+        None => return body.into(),
+    };
+    let (field, id) = match (serde_json::to_vec(field), serde_json::to_vec(&id)) {
+        (Ok(field), Ok(id)) => (field, id),
+        _ => return body.into(),
+    };
+
+    let mut result = Vec::with_capacity(body.len() + field.len() + id.len() + 2);
+    result.extend_from_slice(&body[..start]);
+    result.extend_from_slice(&field);
+    result.push(b':');
+    result.extend_from_slice(&id);
+    if !keys.is_empty {
+        result.push(b',');
+    }
+    result.extend_from_slice(&body[start..]);
+    result.into()
+}
+
+fn is_json<B>(resp: &Response<B>) -> bool {
+    let content_type = match resp.headers().get(http::header::CONTENT_TYPE).and_then(|value| value.to_str().ok()) {
+        Some(content_type) => content_type,
+        None => return false,
+    };
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("application/json")
+}
+
+///Layer for writing request id into JSON response's body.
+///
+///It must be placed inside of `GenerateRequestIdLayer`, as ID of type `O` is taken from request's extensions.
+///Only responses with `content-type: application/json` are modified, any other response is passed as it is.
+///
+///JSON body is buffered fully, and if it is JSON object, field with ID is added to it, which means:
+///
+///- Response's `content-length` is removed as body's length changes;
+///- Body is not streamed, so this layer is not suitable for large bodies;
+///- Body that is not valid JSON object, or already has field with the same name, is sent unchanged.
+///
+///Field is inserted as the first one of the object, while the rest of body is kept byte for byte (e.g. order of keys and precision of numbers).
+///
+///Header continues to be written by `GenerateRequestIdLayer`, unless it is disabled.
+pub struct RequestIdJsonLayer<O> {
+    field: &'static str,
+    _id: PhantomData<fn() -> O>,
+}
+
+impl<O> RequestIdJsonLayer<O> {
+    #[inline(always)]
+    ///Creates new instance
+    pub const fn new() -> Self {
+        Self {
+            field: JSON_FIELD,
+            _id: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    ///Specifies name of JSON field to write ID into.
+    ///
+    ///Defaults to `request_id`.
+    pub const fn field(mut self, field: &'static str) -> Self {
+        self.field = field;
+        self
+    }
+}
+
+impl<O> Default for RequestIdJsonLayer<O> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O> Clone for RequestIdJsonLayer<O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            field: self.field,
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<O> fmt::Debug for RequestIdJsonLayer<O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RequestIdJsonLayer").field("field", &self.field).finish()
+    }
+}
+
+impl<S, O> Layer<S> for RequestIdJsonLayer<O> {
+    type Service = RequestIdJson<S, O>;
+
+    #[inline(always)]
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdJson {
+            inner,
+            field: self.field,
+            _id: PhantomData,
+        }
+    }
+}
+
+///Service for writing request id into JSON response's body.
+///
+///See `RequestIdJsonLayer` for details.
+pub struct RequestIdJson<S, O> {
+    inner: S,
+    field: &'static str,
+    _id: PhantomData<fn() -> O>,
+}
+
+impl<S: Clone, O> Clone for RequestIdJson<S, O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            field: self.field,
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, O> fmt::Debug for RequestIdJson<S, O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RequestIdJson").field("inner", &self.inner).field("field", &self.field).finish()
+    }
+}
+
+impl<ReqBody, ResBody, S: Service<Request<ReqBody>, Response = Response<ResBody>>, O: fmt::Display + Send + Sync + 'static> Service<Request<ReqBody>> for RequestIdJson<S, O> {
+    type Response = Response<JsonBody<ResBody>>;
+    type Error = S::Error;
+    type Future = JsonFut<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, ctx: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(ctx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let id = req.extensions().get::<O>().map(|id| id.to_string());
+
+        JsonFut {
+            inner: self.inner.call(req),
+            field: self.field,
+            id,
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    ///Future wrapping response's body into `JsonBody`.
+    pub struct JsonFut<F> {
+        #[pin]
+        inner: F,
+        field: &'static str,
+        id: Option<String>,
+    }
+}
+
+impl<B, E, F: Future<Output = Result<Response<B>, E>>> Future for JsonFut<F> {
+    type Output = Result<Response<JsonBody<B>>, E>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+        let this = self.project();
+
+        let mut resp = match Future::poll(this.inner, ctx) {
+            task::Poll::Ready(resp) => resp?,
+            task::Poll::Pending => return task::Poll::Pending,
+        };
+
+        let state = match this.id.take() {
+            Some(id) if is_json(&resp) => {
+                resp.headers_mut().remove(http::header::CONTENT_LENGTH);
+                JsonState::Buffer {
+                    field: this.field,
+                    id,
+                    buffer: Vec::new(),
+                }
+            },
+            _ => JsonState::Forward,
+        };
+
+        task::Poll::Ready(Ok(resp.map(|inner| JsonBody {
+            inner,
+            state,
+        })))
+    }
+}
+
+enum JsonState {
+    Forward,
+    Buffer {
+        field: &'static str,
+        id: String,
+        buffer: Vec<u8>,
+    },
+    Done,
+}
+
+pin_project_lite::pin_project! {
+    ///Body, which writes request id into JSON object of inner body.
+    ///
+    ///Inner body is buffered fully before ID is written, while trailers are passed as they are.
+    pub struct JsonBody<B> {
+        #[pin]
+        inner: B,
+        state: JsonState,
+    }
+}
+
+impl<B> JsonBody<B> {
+    #[inline(always)]
+    ///Returns reference to inner body.
+    pub fn get_ref(&self) -> &B {
+        &self.inner
+    }
+
+    #[inline(always)]
+    ///Returns inner body, dropping any buffered data.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: Default> Default for JsonBody<B> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            inner: B::default(),
+            state: JsonState::Forward,
+        }
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for JsonBody<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let buffering = matches!(self.state, JsonState::Buffer { .. });
+        fmt.debug_struct("JsonBody").field("inner", &self.inner).field("buffering", &buffering).finish()
+    }
+}
+
+impl<B: http_body::Body> http_body::Body for JsonBody<B> {
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_data(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+
+        loop {
+            let buffer = match this.state {
+                JsonState::Forward => return match this.inner.poll_data(ctx) {
+                    task::Poll::Ready(Some(Ok(mut data))) => task::Poll::Ready(Some(Ok(data.copy_to_bytes(data.remaining())))),
+                    task::Poll::Ready(Some(Err(error))) => task::Poll::Ready(Some(Err(error))),
+                    task::Poll::Ready(None) => task::Poll::Ready(None),
+                    task::Poll::Pending => task::Poll::Pending,
+                },
+                JsonState::Buffer { buffer, .. } => buffer,
+                JsonState::Done => return task::Poll::Ready(None),
+            };
+
+            match this.inner.as_mut().poll_data(ctx) {
+                task::Poll::Ready(Some(Ok(mut data))) => while data.has_remaining() {
+                    let chunk = data.chunk();
+                    let len = chunk.len();
+                    buffer.extend_from_slice(chunk);
+                    data.advance(len);
+                },
+                task::Poll::Ready(Some(Err(error))) => return task::Poll::Ready(Some(Err(error))),
+                task::Poll::Ready(None) => match mem::replace(this.state, JsonState::Done) {
+                    JsonState::Buffer { field, id, buffer } => return task::Poll::Ready(Some(Ok(inject_into_bytes(buffer, field, id)))),
+                    _ => unreachable!(),
+                },
+                task::Poll::Pending => return task::Poll::Pending,
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn poll_trailers(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(ctx)
+    }
+
+    #[inline(always)]
+    fn is_end_stream(&self) -> bool {
+        match self.state {
+            JsonState::Buffer { .. } => false,
+            _ => self.inner.is_end_stream(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> http_body::SizeHint {
+        match self.state {
+            JsonState::Buffer { .. } => http_body::SizeHint::default(),
+            _ => self.inner.size_hint(),
+        }
+    }
+}
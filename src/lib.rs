@@ -6,9 +6,26 @@
 //!
 //!To cover as many strategies as possible, it is best to use `String` type that can accept any type of id from client.
 //!
+//!ID is written into response header, unless its textual representation contains control characters or leading/trailing whitespace.
+//!Such values are handled according to `InvalidValue` configured on the layer.
+//!
 //!## Features:
 //!
+//!- `std` - Enables features relying on standard library, like `SystemClock`.
 //!- `uuid` - Enables UUID based generator.
+//!- `tower` - Enables conversion of layer into `tower::util::BoxLayer`.
+//!- `b3` - Enables B3 single header based ID.
+//!- `amzn` - Enables AWS X-Ray `x-amzn-trace-id` header based ID. Implies `std`.
+//!- `random` - Enables generators of random tokens.
+//!- `test-util` - Enables utilities to test own ID generators.
+//!- `zeroize` - Enables `SecretId`, which is wiped from memory on drop.
+//!- `scope` - Enables access to ID of currently processed request via `current_request_id`.
+//!- `trailer` - Enables writing ID into response's trailers.
+//!- `hash` - Enables writing salted hash of ID into response header and its constant-time verification.
+//!- `axum` - Enables extraction of `RequestId` within `axum` handlers.
+//!- `json` - Enables writing ID into JSON response's body.
+//!- `rand` - Enables generator of random tokens, using user provided `rand::RngCore`.
+//!- `metrics` - Enables counters of generated and accepted IDs via `metrics` crate.
 //!
 //!## Defining own ID generator:
 //!
@@ -38,11 +55,19 @@
 
 #![no_std]
 #![warn(missing_docs)]
-#![cfg_attr(feature = "cargo-clippy", allow(clippy::style))]
+#![allow(clippy::style)]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 mod utils;
+mod error;
+pub use error::RequestIdError;
+mod request;
+pub use request::RequestRef;
+mod request_id;
+pub use request_id::{RequestId, RequestIdStr, ParentId, TaggedGenerator};
 
 pub use http;
 pub use tower_layer;
@@ -52,161 +77,1984 @@ use core::{fmt, task};
 use core::pin::Pin;
 use core::future::Future;
 use core::marker::PhantomData;
+use alloc::vec::Vec;
+use alloc::string::String;
 
-use http::{Response, Request};
+use http::{Method, Response, Request};
 use tower_layer::Layer;
 use tower_service::Service;
 
 ///Header name for Request id
 pub const HEADER_NAME: &str = "x-request-id";
+//Pre-built name to avoid copying it on every insertion.
+const HEADER: http::HeaderName = http::HeaderName::from_static(HEADER_NAME);
 
+mod static_id;
+pub use static_id::{StaticId, StaticGenerator, ConstGenerator};
+mod seeded;
+pub use seeded::SeededGenerator;
+mod shared;
+pub use shared::{SharedId, SharedGenerator};
+mod retry;
+pub use retry::RetryGen;
+mod hybrid;
+pub use hybrid::{HybridId, HybridGenerator};
+mod registry;
+pub use registry::{GeneratorRegistry, DynIdGen};
+mod host_tagged;
+pub use host_tagged::HostTaggedGenerator;
+mod checksum;
+pub use checksum::{ChecksummedGenerator, verify_checksum};
+mod fs_safe;
+pub use fs_safe::{FsSafeGenerator, is_fs_safe};
+mod valid;
+pub use valid::AlwaysValidHeader;
+mod scheme;
+pub use scheme::{IdScheme, StringScheme, CounterScheme};
+mod clock;
+pub use clock::Clock;
+#[cfg(feature = "std")]
+pub use clock::SystemClock;
+#[cfg(feature = "std")]
+mod thread_tagged;
+#[cfg(feature = "std")]
+pub use thread_tagged::ThreadTaggedGenerator;
+#[cfg(feature = "std")]
+mod thread_local;
+#[cfg(feature = "std")]
+pub use thread_local::ThreadLocalGenerator;
+#[cfg(feature = "std")]
+mod on_error;
+#[cfg(feature = "std")]
+pub use on_error::{RequestIdOnErrorLayer, RequestIdOnError, RequestIdOnErrorFut, ErrorWithId};
+#[cfg(feature = "std")]
+mod limit;
+#[cfg(feature = "std")]
+mod buffered;
+#[cfg(feature = "std")]
+pub use buffered::BufferedGenerator;
+mod propagate;
+pub use propagate::{PropagateRequestIdLayer, PropagateRequestId};
+#[cfg(feature = "trailer")]
+mod trailer;
+#[cfg(feature = "trailer")]
+pub use trailer::{RequestIdTrailerLayer, RequestIdTrailer, TrailerFut, TrailerBody};
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+pub use json::{inject_request_id, RequestIdJsonLayer, RequestIdJson, JsonFut, JsonBody, JSON_FIELD};
+#[cfg(feature = "random")]
+mod base62;
+#[cfg(feature = "random")]
+pub use base62::{Base62, Base62Generator, InvalidBase62};
+#[cfg(feature = "random")]
+mod salted;
+#[cfg(feature = "random")]
+pub use salted::SaltedCounterGenerator;
+#[cfg(feature = "random")]
+mod time_sortable;
+#[cfg(feature = "random")]
+pub use time_sortable::TimeSortableGenerator;
+#[cfg(feature = "random")]
+mod hex;
+#[cfg(feature = "random")]
+pub use hex::{RawId, FixedHexGenerator, InvalidHex};
+#[cfg(feature = "rand")]
+mod rng;
+#[cfg(feature = "rand")]
+pub use rng::RngGenerator;
+#[cfg(all(feature = "std", feature = "random"))]
+mod boot_scoped;
+#[cfg(all(feature = "std", feature = "random"))]
+pub use boot_scoped::BootScopedGenerator;
+#[cfg(feature = "b3")]
+mod b3;
+#[cfg(feature = "b3")]
+pub use b3::{B3, B3Generator, Sampling, InvalidB3, B3_HEADER_NAME};
+#[cfg(feature = "amzn")]
+mod amzn;
+#[cfg(feature = "amzn")]
+pub use amzn::{AmznTraceId, AmznTraceIdGenerator, InvalidAmznTraceId, AMZN_TRACE_ID_HEADER_NAME};
 #[cfg(feature = "uuid")]
 mod uuid;
 #[cfg(feature = "uuid")]
-pub use uuid::{Uuid, UuidGenerator};
+pub use uuid::{Uuid, UuidGenerator, LocalUuidGenerator, UuidVersion};
+#[cfg(feature = "zeroize")]
+mod secret;
+#[cfg(feature = "zeroize")]
+pub use secret::SecretId;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "hash")]
+mod hash;
+#[cfg(feature = "hash")]
+pub use hash::verify_response_hash;
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "axum")]
+pub use axum::MissingRequestId;
+#[cfg(feature = "scope")]
+mod scope;
+#[cfg(feature = "scope")]
+pub use scope::current_request_id;
+
+#[cfg(feature = "metrics")]
+///Name of `metrics` counter of IDs, generated by the middleware.
+pub const GENERATED_COUNTER: &str = "request_id_generated";
+#[cfg(feature = "metrics")]
+///Name of `metrics` counter of IDs, accepted from client.
+pub const ACCEPTED_COUNTER: &str = "request_id_accepted";
+
+///Default expected length of ID's textual representation.
+pub const DEFAULT_LEN_HINT: usize = 16;
 
 ///Trait to generate ID
-pub trait IdGen<Output>: Sized {
+///
+///It is object safe, allowing to select generator at runtime.
+///Since layer requires generator to be `Clone`, type-erased generator should be stored as `Arc<dyn IdGen<O> + Send + Sync>`.
+pub trait IdGen<Output> {
     ///Generate ID
     fn gen(&self) -> Output;
+
+    #[inline(always)]
+    ///Generates ID for the request, which lacks valid ID.
+    ///
+    ///This allows generator to derive ID from request itself.
+    ///Note that ID pre-generated in `poll_ready` is created via `gen`.
+    ///Defaults to `gen`.
+    fn gen_for(&self, _req: RequestRef<'_>) -> Output {
+        self.gen()
+    }
+
+    #[inline(always)]
+    ///Returns expected length of `Output`'s textual representation in bytes.
+    ///
+    ///It is used to pre-allocate buffer for response's header value.
+    ///Defaults to `DEFAULT_LEN_HINT`.
+    fn len_hint(&self) -> usize {
+        DEFAULT_LEN_HINT
+    }
+
+    #[inline(always)]
+    ///Returns ready to use response header value for the `id`, if generator is able to provide it.
+    ///
+    ///When `Some`, formatting of ID is bypassed completely, including padding and validation.
+    ///Defaults to `None`.
+    fn header_value(&self, _id: &Output) -> Option<http::HeaderValue> {
+        None
+    }
+
+    #[inline(always)]
+    ///Returns function to create ready to use response header value for the `id`.
+    ///
+    ///This is lazy counterpart of `header_value`: function is invoked within response's future, hence it has no access to generator's state.
+    ///When it returns `Some`, formatting of ID is bypassed completely, including padding and validation.
+    ///Defaults to `None`.
+    fn header_value_fn(&self) -> Option<fn(&Output) -> Option<http::HeaderValue>> {
+        None
+    }
+
+    #[inline(always)]
+    ///Returns function to write textual representation of `Output` directly into response header's buffer.
+    ///
+    ///This allows to avoid intermediate allocations, which `Display` implementation may require.
+    ///Function is invoked within response's future, where generator is not accessible, hence it has no access to generator's state.
+    ///Written value is subject to the same padding and validation as `Display` output.
+    ///Defaults to `None`, meaning `Display` implementation is used.
+    fn write_id(&self) -> Option<fn(&Output, &mut dyn fmt::Write) -> fmt::Result> {
+        None
+    }
+
+    #[inline(always)]
+    ///Checks whether generator is able to produce ID.
+    ///
+    ///It is consulted by `GenerateRequestId::poll_ready`, before inner service's readiness.
+    ///Generator with limited capacity should return `Pending` and wake `ctx` once capacity is available.
+    ///Defaults to always being ready.
+    fn poll_ready(&self, _ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        task::Poll::Ready(())
+    }
 }
 
-///Describes Request's ID type
+///Borrowed generator.
+///
+///Note that service requires generator to be `'static`, hence only `&'static G` can be used with the layer.
+impl<G: IdGen<O> + ?Sized, O> IdGen<O> for &G {
+    #[inline(always)]
+    fn gen(&self) -> O {
+        G::gen(self)
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> O {
+        G::gen_for(self, req)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        G::len_hint(self)
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &O) -> Option<http::HeaderValue> {
+        G::header_value(self, id)
+    }
+
+    #[inline(always)]
+    fn header_value_fn(&self) -> Option<HeaderValueFn<O>> {
+        G::header_value_fn(self)
+    }
+
+    #[inline(always)]
+    fn write_id(&self) -> Option<WriteIdFn<O>> {
+        G::write_id(self)
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        G::poll_ready(self, ctx)
+    }
+}
+
+///Shared generator, which is useful when generator's state must not be cloned.
+impl<G: IdGen<O> + ?Sized, O> IdGen<O> for alloc::sync::Arc<G> {
+    #[inline(always)]
+    fn gen(&self) -> O {
+        G::gen(self)
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> O {
+        G::gen_for(self, req)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        G::len_hint(self)
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &O) -> Option<http::HeaderValue> {
+        G::header_value(self, id)
+    }
+
+    #[inline(always)]
+    fn header_value_fn(&self) -> Option<HeaderValueFn<O>> {
+        G::header_value_fn(self)
+    }
+
+    #[inline(always)]
+    fn write_id(&self) -> Option<WriteIdFn<O>> {
+        G::write_id(self)
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        G::poll_ready(self, ctx)
+    }
+}
+
+///Type-erased generator.
+///
+///Note that it is not `Clone`, hence it cannot be used with the layer directly.
+///Use `Arc<dyn IdGen<O> + Send + Sync>` instead.
+impl<O> IdGen<O> for alloc::boxed::Box<dyn IdGen<O> + Send + Sync> {
+    #[inline(always)]
+    fn gen(&self) -> O {
+        (**self).gen()
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> O {
+        (**self).gen_for(req)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        (**self).len_hint()
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &O) -> Option<http::HeaderValue> {
+        (**self).header_value(id)
+    }
+
+    #[inline(always)]
+    fn header_value_fn(&self) -> Option<HeaderValueFn<O>> {
+        (**self).header_value_fn()
+    }
+
+    #[inline(always)]
+    fn write_id(&self) -> Option<WriteIdFn<O>> {
+        (**self).write_id()
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        (**self).poll_ready(ctx)
+    }
+}
+
+///Describes Request's ID type, which is never accepted from client.
 ///
 ///It has following requirements:
 ///
 ///- `IdGen` must be implemented for type that generates ID.
-///- `ID` can be created from string by means of `FromStr` trait.
 ///- `ID` should be write-able in order to store it in outgoing response.
 ///- `ID` should be `Clone`-able in order to be copied to write it in response header.
-pub trait IdType<G: IdGen<Self>>: Sized + core::str::FromStr + fmt::Display + Clone {
+///
+///Such ID can be used with layer created via `GenerateRequestIdLayer::generate_only`.
+pub trait GenerateOnlyId<G: IdGen<Self>>: Sized + fmt::Display + Clone {
+}
+
+impl<G: IdGen<T> + Sized, T: Sized + fmt::Display + Clone> GenerateOnlyId<G> for T {
+}
+
+///Describes Request's ID type
+///
+///In addition to `GenerateOnlyId` requirements, `ID` can be created from string by means of `FromStr` trait.
+pub trait IdType<G: IdGen<Self>>: GenerateOnlyId<G> + core::str::FromStr {
 }
 
 impl<G: IdGen<T> + Sized, T: Sized + core::str::FromStr + fmt::Display + Clone> IdType<G> for T {
 }
 
-#[derive(Clone, Copy, Debug)]
-///Layer for adding request id.
+///Marker of ID types, which can be created out of any textual header's value.
 ///
-///See module documentation for details.
-pub struct GenerateRequestIdLayer<G, O> {
-    gen: G,
-    _out: PhantomData<O>,
+///Layer created via `GenerateRequestIdLayer::new_infallible` converts client's ID directly, bypassing `FromStr`.
+///Header's value, which is not valid string, is still considered absent.
+pub trait FromHeaderStr: Sized {
+    ///Creates ID out of header's value.
+    fn from_header_str(text: &str) -> Self;
 }
 
-impl<G, O> GenerateRequestIdLayer<G, O> {
+impl FromHeaderStr for String {
     #[inline(always)]
-    ///Creates new instance
-    pub const fn new(gen: G) -> Self {
-        Self {
-            gen,
-            _out: PhantomData,
-        }
+    fn from_header_str(text: &str) -> Self {
+        String::from(text)
     }
 }
 
-impl<G: Default, O> Default for GenerateRequestIdLayer<G, O> {
-    fn default() -> Self {
-        Self {
-            gen: Default::default(),
-            _out: PhantomData,
-        }
+#[inline(always)]
+///Generates new ID, the same way as middleware does when request has no ID.
+///
+///This is useful to produce IDs outside of request's context (e.g. within background jobs).
+pub fn generate<G: IdGen<O>, O>(gen: &G) -> O {
+    gen.gen()
+}
+
+#[derive(Clone, Copy, Debug)]
+//Options of incoming ID parsing.
+struct ParseOptions {
+    accept_empty: bool,
+    trim: bool,
+    structured_field: bool,
+}
+
+impl ParseOptions {
+    const DEFAULT: Self = Self {
+        accept_empty: false,
+        trim: true,
+        structured_field: false,
+    };
+}
+
+#[inline]
+fn from_str<O: core::str::FromStr>(text: &str) -> Option<O> {
+    O::from_str(text).ok()
+}
+
+#[inline]
+fn from_header_str<O: FromHeaderStr>(text: &str) -> Option<O> {
+    Some(O::from_header_str(text))
+}
+
+#[inline]
+fn parse_headers<O>(headers: &http::HeaderMap, name: &http::HeaderName, options: ParseOptions, parse: fn(&str) -> Option<O>) -> Option<O> {
+    match headers.get(name) {
+        Some(header) => match header.to_str() {
+            Ok(header) => parse_value(header, options, parse),
+            Err(_) => None,
+        },
+        None => None,
     }
 }
 
-impl<S, G: IdGen<O> + Clone, O: IdType<G>> Layer<S> for GenerateRequestIdLayer<G, O> {
-    type Service = GenerateRequestId<S, G, O>;
+fn parse_bin_headers<O>(headers: &http::HeaderMap, name: &http::HeaderName, options: ParseOptions, parse: fn(&str) -> Option<O>) -> Option<O> {
+    let value = headers.get(name).and_then(|header| utils::base64_decode(header.as_bytes()))?;
+    match core::str::from_utf8(&value) {
+        Ok(value) => parse_value(value, options, parse),
+        Err(_) => None,
+    }
+}
 
-    #[inline(always)]
-    fn layer(&self, inner: S) -> Self::Service {
-        GenerateRequestId::new(inner, self.gen.clone())
+fn parse_query<O>(uri: &http::Uri, name: &str, options: ParseOptions, parse: fn(&str) -> Option<O>) -> Option<O> {
+    match uri.query().and_then(|query| utils::query_param(query, name)) {
+        Some(value) => parse_value(value, options, parse),
+        None => None,
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-///Service for adding request id.
+#[inline]
+fn parse_value<O>(value: &str, options: ParseOptions, parse: fn(&str) -> Option<O>) -> Option<O> {
+    let trimmed = value.trim_matches(|ch| ch == ' ' || ch == '\t');
+    let value = match options.trim {
+        true => trimmed,
+        false => value,
+    };
+
+    match !options.accept_empty && trimmed.is_empty() {
+        true => None,
+        false => match options.structured_field {
+            true => parse(&utils::sf_item(value)?),
+            false => parse(value),
+        },
+    }
+}
+
+#[inline]
+///Resolves ID from `headers`, the same way as middleware does by default.
 ///
-///See module documentation for details.
-pub struct GenerateRequestId<S, G, O> {
-    inner: S,
-    gen: G,
-    _out: PhantomData<O>,
+///If `x-request-id` is present and valid, then it is used as ID.
+///Otherwise new ID is generated.
+///Leading and trailing whitespace is trimmed, and empty header is considered absent.
+pub fn resolve_from_headers<G: IdGen<O>, O: IdType<G>>(headers: &http::HeaderMap, gen: &G) -> O {
+    match parse_headers(headers, &HEADER, ParseOptions::DEFAULT, from_str) {
+        Some(id) => id,
+        None => gen.gen(),
+    }
 }
 
-impl<S, G, O> GenerateRequestId<S, G, O> {
-    #[inline(always)]
-    ///Creates new instance
-    pub const fn new(inner: S, gen: G) -> Self {
-        Self {
-            inner,
-            gen,
-            _out: PhantomData,
-        }
+#[inline]
+///Formats `id` into header value, the same way as middleware does by default.
+///
+///Returns error if textual representation of `id` is not safe to be used as header value.
+pub fn to_header_value<T: fmt::Display>(id: &T) -> Result<http::HeaderValue, RequestIdError> {
+    match format_header_value(id, DEFAULT_LEN_HINT, 0, InvalidValue::Skip) {
+        Some(value) => Ok(value),
+        None => Err(RequestIdError::InvalidHeaderValue),
     }
 }
 
-//use separate type parameter for request and response bodies.
-//to make sure user is free to use whatever handler he wishes.
-impl<ReqBody, ResBody, S: Service<Request<ReqBody>, Response = Response<ResBody>>, O: IdType<G> + Send + Sync + 'static, G: IdGen<O> + Clone + Send + Sync + 'static> Service<Request<ReqBody>> for GenerateRequestId<S, G, O> {
-    type Response = S::Response;
-    type Error = S::Error;
-    type Future = ResponseFut<S::Future, O>;
+#[derive(Clone, Copy)]
+//Describes how `insert_request_id` stores ID within request's extensions.
+struct InsertOptions {
+    reuse_extension: bool,
+    reuse_any_extension: bool,
+    insert_extension: bool,
+    force_overwrite: bool,
+    store_string: bool,
+    #[cfg(feature = "scope")]
+    scope_id: bool,
+    //Whether ID is returned to caller, otherwise it is moved into extensions.
+    keep_id: bool,
+}
 
-    #[inline]
-    fn poll_ready(&mut self, ctx: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
-        self.inner.poll_ready(ctx)
-    }
+impl InsertOptions {
+    const DEFAULT: Self = Self {
+        reuse_extension: true,
+        reuse_any_extension: false,
+        insert_extension: true,
+        force_overwrite: true,
+        store_string: false,
+        #[cfg(feature = "scope")]
+        scope_id: false,
+        keep_id: true,
+    };
+}
 
-    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
-        let id = match req.headers().get(HEADER_NAME) {
-            Some(header) => match header.to_str() {
-                Ok(header) => match O::from_str(header) {
-                    Ok(id) => id,
-                    Err(_) => self.gen.gen(),
-                },
-                Err(_) => self.gen.gen(),
+//ID, stored by `insert_request_id`.
+struct InsertedRequestId<O> {
+    //Present only if `keep_id` is set.
+    id: Option<O>,
+    #[cfg(feature = "scope")]
+    scope_id: scope::ScopedId,
+}
+
+//Re-uses ID from extensions or resolves new one, storing it according to `options`.
+//
+//ID is cloned only once, when both extensions and caller need it.
+#[cfg_attr(not(feature = "scope"), allow(unused_variables))]
+fn insert_request_id<B, O: fmt::Display + Clone + Send + Sync + 'static, E>(req: &mut Request<B>, options: InsertOptions, resolve: impl FnOnce(&mut Request<B>) -> Result<O, E>) -> Result<InsertedRequestId<O>, E> {
+    let reused = match options.reuse_extension {
+        true => reused_id::<O>(req.extensions(), options.reuse_any_extension),
+        false => None,
+    };
+
+    let (id, id_str, scope_id) = match reused {
+        Some(id) => (
+            match options.keep_id {
+                true => Some(id.clone()),
+                false => None,
             },
-            None => self.gen.gen(),
-        };
+            stored_string(id, options),
+            scoped_id(id, options),
+        ),
+        None => {
+            let id = resolve(req)?;
+            let id_str = stored_string(&id, options);
+            let scope_id = scoped_id(&id, options);
+            let id = match (options.insert_extension, options.keep_id) {
+                (true, true) => {
+                    insert_extension(req, id.clone(), options.force_overwrite);
+                    Some(id)
+                },
+                (true, false) => {
+                    insert_extension(req, id, options.force_overwrite);
+                    None
+                },
+                (false, true) => Some(id),
+                (false, false) => None,
+            };
+            (id, id_str, scope_id)
+        },
+    };
+    if let Some(id_str) = id_str {
+        req.extensions_mut().insert(id_str);
+    }
 
-        req.extensions_mut().insert(id.clone());
-        ResponseFut {
-            inner: self.inner.call(req),
-            id,
-        }
+    Ok(InsertedRequestId {
+        id,
+        #[cfg(feature = "scope")]
+        scope_id,
+    })
+}
+
+#[inline]
+fn stored_string<O: fmt::Display>(id: &O, options: InsertOptions) -> Option<RequestIdStr> {
+    match options.store_string {
+        true => Some(RequestIdStr(alloc::string::ToString::to_string(id))),
+        false => None,
+    }
+}
+
+#[cfg(feature = "scope")]
+#[inline]
+fn scoped_id<O: Clone + Send + Sync + 'static>(id: &O, options: InsertOptions) -> scope::ScopedId {
+    match options.scope_id {
+        true => Some(alloc::boxed::Box::new(id.clone())),
+        false => None,
     }
 }
 
-///Future adding request-id to list of response's headers.
-pub struct ResponseFut<F, T> {
-    inner: F,
-    id: T
+#[cfg(not(feature = "scope"))]
+#[inline(always)]
+fn scoped_id<O>(_: &O, _: InsertOptions) {
 }
 
-impl<ResBody, E, F: Future<Output = Result<Response<ResBody>, E>>, T: fmt::Display> Future for ResponseFut<F, T> {
-    type Output = F::Output;
+//Marker of ID, inserted into request's extensions by this crate.
+//
+//Bare `O` may be inserted by unrelated middleware for own purposes, hence it is not re-used without marker.
+struct InsertedId<O> {
+    //Whether ID is stored as `RequestId<O>`, due to conflicting extension.
+    wrapped: bool,
+    _id: PhantomData<fn() -> O>,
+}
+
+//Returns ID inserted by this crate, or any `O` if `any` is set.
+fn reused_id<O: Send + Sync + 'static>(extensions: &http::Extensions, any: bool) -> Option<&O> {
+    match extensions.get::<InsertedId<O>>() {
+        Some(inserted) if inserted.wrapped => extensions.get::<RequestId<O>>().map(|id| &id.0),
+        Some(_) => extensions.get::<O>(),
+        None if any => extensions.get::<O>(),
+        None => None,
+    }
+}
+
+//Stores ID in extensions, falling back to `RequestId` wrapper when conflicting extension is already present.
+fn insert_extension<B, O: Send + Sync + 'static>(req: &mut Request<B>, id: O, force_overwrite: bool) {
+    let wrapped = !force_overwrite && req.extensions().get::<O>().is_some();
+    if wrapped {
+        req.extensions_mut().insert(RequestId::<O>(id));
+    } else {
+        req.extensions_mut().insert(id);
+    }
+    req.extensions_mut().insert(InsertedId::<O> {
+        wrapped,
+        _id: PhantomData,
+    });
+}
+
+///Sets ID of the request, the same way as middleware does by default.
+///
+///This allows to use request ID with frameworks, which are not based on `tower`.
+///
+///If request's extensions already have ID, inserted by this function or by middleware, then it is returned as it is.
+///Otherwise ID is resolved from headers, or generated, and inserted into request's extensions.
+pub fn set_request_id<B, G: IdGen<O>, O: IdType<G> + Send + Sync + 'static>(req: &mut Request<B>, gen: &G) -> O {
+    let id = insert_request_id(req, InsertOptions::DEFAULT, |req| Ok::<_, core::convert::Infallible>(match parse_headers(req.headers(), &HEADER, ParseOptions::DEFAULT, from_str) {
+        Some(id) => id,
+        None => gen.gen_for(RequestRef::new(req)),
+    }));
+    match id {
+        Ok(InsertedRequestId { id: Some(id), .. }) => id,
+        //ID is always kept
+        Ok(InsertedRequestId { id: None, .. }) => unreachable!(),
+        Err(error) => match error {},
+    }
+}
+
+#[inline]
+///Writes ID into response's headers, the same way as middleware does by default.
+///
+///This is counterpart of `set_request_id`, and response is left unchanged on error.
+pub fn set_response_id<B, T: fmt::Display>(resp: &mut Response<B>, id: &T) -> Result<(), RequestIdError> {
+    let value = to_header_value(id)?;
+    resp.headers_mut().insert(HEADER, value);
+    Ok(())
+}
+
+///Makes `child` request inherit ID of the `parent` request.
+///
+///This is useful when request is fanned out into multiple sub-requests, constructed manually.
+///ID is taken from `parent`'s extensions and written into `child`'s `x-request-id` header, as well as its extensions.
+///
+///Returns `Ok(false)` if `parent` has no ID, and `child` is left unchanged on error.
+pub fn inherit_request_id<O: fmt::Display + Clone + Send + Sync + 'static, A, B>(parent: &Request<A>, child: &mut Request<B>) -> Result<bool, RequestIdError> {
+    let id = match parent.extensions().get::<O>() {
+        Some(id) => id,
+        None => return Ok(false),
+    };
+
+    let value = to_header_value(id)?;
+    child.headers_mut().insert(HEADER, value);
+    insert_extension(child, id.clone(), true);
+    Ok(true)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Describes how to handle ID, which textual representation is not safe to be used as header value.
+///
+///Value is considered unsafe if it contains control characters or has leading/trailing whitespace.
+pub enum InvalidValue {
+    ///Response header is not written.
+    Skip,
+    ///Response header is written with every byte outside of visible ASCII range, as well as `%`, being percent-encoded.
+    Encode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Describes how to resolve ID of particular request.
+pub enum IdPolicy {
+    ///ID from client is used if it is valid, otherwise it is generated.
+    Trust,
+    ///ID is always generated, ignoring ID supplied by client.
+    Generate,
+    ///Request is rejected with specified status, without calling inner service.
+    ///
+    ///Response has empty body and no ID.
+    Reject(http::StatusCode),
+}
+
+#[derive(Clone, Copy, Debug)]
+//Rejection of requests without ID from client.
+struct RequiredHeader {
+    status: http::StatusCode,
+    reject_invalid: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Config {
+    enabled: bool,
+    header_name: http::HeaderName,
+    response_header_name: http::HeaderName,
+    pregenerate: bool,
+    on_invalid_value: InvalidValue,
+    policy: Option<fn(RequestRef<'_>) -> IdPolicy>,
+    required_header: Option<RequiredHeader>,
+    pad_width: usize,
+    value_capacity: Option<usize>,
+    value_sink: Option<ValueSinkFn>,
+    derive_child: bool,
+    per_connection: bool,
+    store_string: bool,
+    reuse_extension: bool,
+    reuse_any_extension: bool,
+    force_overwrite: bool,
+    insert_extension: bool,
+    response_header: bool,
+    #[cfg(feature = "scope")]
+    scope_id: bool,
+    parse: ParseOptions,
+    grpc_bin_header: Option<http::HeaderName>,
+    response_header_when: fn(http::StatusCode) -> bool,
+    //Heap allocated part of config, shared by layer and all its services, so that cloning them never allocates.
+    shared: Option<alloc::sync::Arc<SharedConfig>>,
+}
+
+#[derive(Clone, Debug)]
+struct SharedConfig {
+    generate_for_methods: Vec<Method>,
+    generated: Option<alloc::sync::Arc<core::sync::atomic::AtomicU64>>,
+    #[cfg(feature = "std")]
+    generation_limit: Option<alloc::sync::Arc<limit::TokenBucket>>,
+    query_param: Option<String>,
+    structured_header: Option<(http::HeaderName, utils::Template)>,
+    #[cfg(feature = "hash")]
+    response_hash: Option<bytes::Bytes>,
+}
+
+impl SharedConfig {
+    const DEFAULT: Self = Self {
+        generate_for_methods: Vec::new(),
+        generated: None,
+        #[cfg(feature = "std")]
+        generation_limit: None,
+        query_param: None,
+        structured_header: None,
+        #[cfg(feature = "hash")]
+        response_hash: None,
+    };
+}
+
+//Used in place of absent shared config.
+static NO_SHARED_CONFIG: SharedConfig = SharedConfig::DEFAULT;
 
+impl Config {
     #[inline]
-    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
-        let this = unsafe {
-            Pin::get_unchecked_mut(self)
-        };
-        let fut = unsafe {
-            Pin::new_unchecked(&mut this.inner)
-        };
+    fn shared(&self) -> &SharedConfig {
+        match &self.shared {
+            Some(shared) => shared,
+            None => &NO_SHARED_CONFIG,
+        }
+    }
 
-        let mut resp = match Future::poll(fut, ctx) {
-            task::Poll::Ready(resp) => resp?,
-            task::Poll::Pending => return task::Poll::Pending,
-        };
+    //Builders only: copies shared config if it is already used by someone else.
+    fn shared_mut(&mut self) -> &mut SharedConfig {
+        let shared = self.shared.get_or_insert_with(|| alloc::sync::Arc::new(SharedConfig::DEFAULT));
+        alloc::sync::Arc::make_mut(shared)
+    }
+
+    #[inline]
+    fn generated_count(&self) -> Option<u64> {
+        self.shared().generated.as_ref().map(|generated| generated.load(core::sync::atomic::Ordering::Relaxed))
+    }
+
+    const DEFAULT: Self = Self {
+        enabled: true,
+        header_name: HEADER,
+        response_header_name: HEADER,
+        pregenerate: false,
+        on_invalid_value: InvalidValue::Skip,
+        policy: None,
+        required_header: None,
+        pad_width: 0,
+        value_capacity: None,
+        value_sink: None,
+        derive_child: false,
+        per_connection: false,
+        store_string: false,
+        reuse_extension: true,
+        reuse_any_extension: false,
+        force_overwrite: false,
+        insert_extension: true,
+        response_header: true,
+        #[cfg(feature = "scope")]
+        scope_id: false,
+        parse: ParseOptions::DEFAULT,
+        grpc_bin_header: None,
+        response_header_when: always,
+        shared: None,
+    };
+}
+
+fn grpc_bin_header(name: &http::HeaderName) -> http::HeaderName {
+    let mut bin = String::with_capacity(name.as_str().len() + 4);
+    bin.push_str(name.as_str());
+    bin.push_str("-bin");
+    //Suffix consists of valid characters, so name remains valid.
+    http::HeaderName::from_bytes(bin.as_bytes()).unwrap_or_else(|_| name.clone())
+}
+
+fn always(_: http::StatusCode) -> bool {
+    true
+}
+
+fn is_success(status: http::StatusCode) -> bool {
+    status.is_success()
+}
 
-        let mut header_value = crate::utils::BytesWriter::new();
-        //Retarded implementation could fail intentionally, but there is no reason for proper one to fail when writing into Vec.
-        let _ = fmt::Write::write_fmt(&mut header_value, format_args!("{}", this.id));
+fn is_error(status: http::StatusCode) -> bool {
+    status.is_client_error() || status.is_server_error()
+}
+
+fn is_modified(status: http::StatusCode) -> bool {
+    status != http::StatusCode::NOT_MODIFIED
+}
+
+impl Default for Config {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+type FormatFn<T> = fn(&T, usize, usize, InvalidValue) -> Option<http::HeaderValue>;
+type WriteIdFn<T> = fn(&T, &mut dyn fmt::Write) -> fmt::Result;
+type HeaderValueFn<T> = fn(&T) -> Option<http::HeaderValue>;
+type ValueSinkFn = fn(usize, fmt::Arguments<'_>) -> bytes::Bytes;
+
+//Configuration dependent on ID's type
+struct Hooks<O> {
+    map_id: Option<fn(O) -> O>,
+    response_value: Option<fn(&O) -> String>,
+    format: Option<FormatFn<O>>,
+    parse: Option<fn(&str) -> Option<O>>,
+}
+
+impl<O> Hooks<O> {
+    const DEFAULT: Self = Self {
+        map_id: None,
+        response_value: None,
+        format: None,
+        parse: None,
+    };
+}
+
+impl<O: core::str::FromStr> Hooks<O> {
+    const FROM_STR: Self = Self {
+        map_id: None,
+        response_value: None,
+        format: None,
+        parse: Some(from_str::<O>),
+    };
+}
+
+impl<O: FromHeaderStr> Hooks<O> {
+    const FROM_HEADER_STR: Self = Self {
+        map_id: None,
+        response_value: None,
+        format: None,
+        parse: Some(from_header_str::<O>),
+    };
+}
+
+impl<O> Clone for Hooks<O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O> Copy for Hooks<O> {
+}
+
+impl<O> fmt::Debug for Hooks<O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Hooks").field("map_id", &self.map_id.is_some())
+                                .field("response_value", &self.response_value.is_some())
+                                .field("format", &self.format.is_some())
+                                .field("parse", &self.parse.is_some())
+                                .finish()
+    }
+}
+
+///Response to request, which is rejected without calling inner service.
+///
+///It is type parameter of `GenerateRequestIdLayer`, so that only layers configured to reject requests impose bounds on response's body.
+///Layer switches to `DefaultRejection` when rejection is configured, while `with_rejection` allows to specify own implementation.
+pub trait Rejection<B> {
+    ///Creates body of response with specified `status`.
+    ///
+    ///`None` means that request cannot be rejected, hence it is processed as if rejection is not configured.
+    fn body(status: http::StatusCode) -> Option<B>;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Rejection is not configured, which is compatible with any response body.
+pub struct NoRejection;
 
-        let header_value = header_value.freeze();
-        let header_value = http::HeaderValue::from_maybe_shared(header_value).expect("Generated id is not a valid header value");
-        resp.headers_mut().insert(HEADER_NAME, header_value);
-        task::Poll::Ready(Ok(resp))
+impl<B> Rejection<B> for NoRejection {
+    #[inline(always)]
+    fn body(_: http::StatusCode) -> Option<B> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Rejection response has empty body, hence it requires response body to implement `Default`.
+pub struct DefaultRejection;
+
+impl<B: Default> Rejection<B> for DefaultRejection {
+    #[inline(always)]
+    fn body(_: http::StatusCode) -> Option<B> {
+        Some(B::default())
+    }
+}
+
+#[derive(Clone, Debug)]
+///Layer for adding request id.
+///
+///See module documentation for details.
+pub struct GenerateRequestIdLayer<G, O, R = NoRejection> {
+    gen: G,
+    config: Config,
+    hooks: Hooks<O>,
+    _rejection: PhantomData<fn() -> R>,
+}
+
+impl<G, O: core::str::FromStr> GenerateRequestIdLayer<G, O> {
+    #[inline(always)]
+    ///Creates new instance
+    pub const fn new(gen: G) -> Self {
+        Self {
+            gen,
+            config: Config::DEFAULT,
+            hooks: Hooks::FROM_STR,
+            _rejection: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    ///Creates disabled instance, which service passes requests through as it is.
+    ///
+    ///Neither request's extensions, nor response's headers are modified.
+    ///This allows to switch off request ID without changing type of the layer.
+    pub const fn disabled(gen: G) -> Self {
+        let mut result = Self::new(gen);
+        result.config.enabled = false;
+        result
+    }
+}
+
+impl<G, O: FromHeaderStr> GenerateRequestIdLayer<G, O> {
+    #[inline(always)]
+    ///Creates new instance, which converts ID from client by means of `FromHeaderStr`.
+    ///
+    ///This avoids `FromStr` for IDs, that cannot fail to parse (e.g. `String`).
+    pub const fn new_infallible(gen: G) -> Self {
+        Self {
+            gen,
+            config: Config::DEFAULT,
+            hooks: Hooks::FROM_HEADER_STR,
+            _rejection: PhantomData,
+        }
+    }
+}
+
+impl<G, O> GenerateRequestIdLayer<G, O> {
+    #[inline(always)]
+    ///Creates new instance, which never accepts ID from client, hence `O` is not required to implement `FromStr`.
+    ///
+    ///ID is always generated, unless parser is specified via `parse_with`.
+    pub const fn generate_only(gen: G) -> Self {
+        Self {
+            gen,
+            config: Config::DEFAULT,
+            hooks: Hooks::DEFAULT,
+            _rejection: PhantomData,
+        }
+    }
+}
+
+impl<G, O, R> GenerateRequestIdLayer<G, O, R> {
+    #[inline]
+    ///Replaces generator, preserving rest of configuration.
+    pub fn with_generator<N>(self, gen: N) -> GenerateRequestIdLayer<N, O, R> {
+        GenerateRequestIdLayer {
+            gen,
+            config: self.config,
+            hooks: self.hooks,
+            _rejection: PhantomData,
+        }
+    }
+
+    #[inline]
+    ///Specifies response to requests, which are rejected according to configuration.
+    ///
+    ///Configuring rejection (e.g. `require_header`) sets `DefaultRejection`, hence this must be called afterwards.
+    pub fn with_rejection<N>(self) -> GenerateRequestIdLayer<G, O, N> {
+        GenerateRequestIdLayer {
+            gen: self.gen,
+            config: self.config,
+            hooks: self.hooks,
+            _rejection: PhantomData,
+        }
+    }
+
+    #[inline]
+    ///Specifies header name to read ID from and write it into.
+    ///
+    ///Header names are case-insensitive: `HeaderName` is always normalized to lower case,
+    ///hence incoming header is matched regardless of its casing, and response header is always written in lower case.
+    ///Only value of the header is case-sensitive, and it is passed to `FromStr` as it is.
+    ///
+    ///Defaults to `x-request-id`.
+    pub fn header_name(mut self, name: http::HeaderName) -> Self {
+        self.config.response_header_name = name.clone();
+        self.request_header_name(name)
+    }
+
+    #[inline]
+    ///Specifies header name to read ID from, leaving response header name unchanged.
+    ///
+    ///This allows to accept ID from header set by other system (e.g. `x-amzn-trace-id`), while writing it under own name.
+    pub fn request_header_name(mut self, name: http::HeaderName) -> Self {
+        if self.config.grpc_bin_header.is_some() {
+            self.config.grpc_bin_header = Some(grpc_bin_header(&name));
+        }
+        self.config.header_name = name;
+        self
+    }
+
+    #[inline]
+    ///Specifies header name to write ID into, leaving request header name unchanged.
+    pub fn response_header_name(mut self, name: http::HeaderName) -> Self {
+        self.config.response_header_name = name;
+        self
+    }
+
+    #[inline]
+    ///Accepts ID from binary gRPC metadata, when header is absent or invalid.
+    ///
+    ///gRPC metadata with `-bin` suffix (e.g. `x-request-id-bin`) carries base64 encoded value, with or without padding.
+    ///Decoded value must be valid unicode string, and it is subject to the same parsing as header.
+    ///Header's textual value, under the name without suffix, still takes precedence.
+    pub fn grpc_metadata(mut self) -> Self {
+        self.config.grpc_bin_header = Some(grpc_bin_header(&self.config.header_name));
+        self
+    }
+
+    #[inline(always)]
+    ///Enables generation of ID within `poll_ready`, taking its cost off the `call`'s path.
+    ///
+    ///Pre-generated ID is used by the next `call` only if request has no valid ID already.
+    ///Otherwise it is kept for the subsequent `call`.
+    ///Pre-generated ID is never shared between clones of the service.
+    pub const fn pregenerate(mut self) -> Self {
+        self.config.pregenerate = true;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    ///Limits generation of IDs to `rate` per second, responding with `429 Too Many Requests` once limit is exceeded.
+    ///
+    ///Limit is token bucket, allowing burst of up to `rate` generated IDs, which is shared between layer and all services it creates.
+    ///Requests with valid ID from client, or with ID re-used from request's extensions, are not limited.
+    ///Flood of requests without ID, which forces generation, may indicate scanning.
+    pub fn generation_limit(mut self, rate: u32) -> GenerateRequestIdLayer<G, O, DefaultRejection> {
+        self.config.shared_mut().generation_limit = Some(alloc::sync::Arc::new(limit::TokenBucket::new(rate)));
+        self.with_rejection()
+    }
+
+    #[inline(always)]
+    ///Additionally stores textual representation of ID in request's extensions as `RequestIdStr`.
+    ///
+    ///This allows code, which is generic over ID's type (e.g. logging), to access ID without knowing its type.
+    ///Note that it requires allocation of `String` per request.
+    pub const fn also_store_string(mut self) -> Self {
+        self.config.store_string = true;
+        self
+    }
+
+    #[inline(always)]
+    ///Keeps valid ID from client as parent, and generates new ID for the request.
+    ///
+    ///Client's ID is stored in request's extensions as `ParentId<O>`, while generated ID is used as usual.
+    ///When client provides no valid ID, there is no parent.
+    pub const fn derive_child(mut self) -> Self {
+        self.config.derive_child = true;
+        self
+    }
+
+    #[inline(always)]
+    ///Uses single ID for all requests, handled by the same service instance.
+    ///
+    ///ID is generated on first request and re-used for subsequent requests, while ID from client is ignored.
+    ///As hyper clones service per connection, this results in ID per connection (e.g. HTTP/1.1 keep-alive).
+    ///Note that middleware, which clones service per request (e.g. `tower::buffer::Buffer`), turns it back into ID per request.
+    ///Requirement of `require_header` is still checked for every request.
+    pub const fn per_connection(mut self) -> Self {
+        self.config.per_connection = true;
+        self
+    }
+
+    #[inline]
+    ///Enables counting of IDs, generated by the middleware.
+    ///
+    ///Counter is shared between layer and all services it creates, and it is accessible via `generated_count`.
+    ///ID accepted from client, or re-used from request's extensions, is not counted.
+    pub fn count_generated(mut self) -> Self {
+        self.config.shared_mut().generated = Some(alloc::sync::Arc::new(core::sync::atomic::AtomicU64::new(0)));
+        self
+    }
+
+    #[inline]
+    ///Returns number of IDs, generated by services created out of this layer.
+    ///
+    ///Returns `None` unless `count_generated` is enabled.
+    pub fn generated_count(&self) -> Option<u64> {
+        self.config.generated_count()
+    }
+
+    #[inline(always)]
+    ///Specifies how to handle ID which is not safe to be written as response header.
+    ///
+    ///Defaults to `InvalidValue::Skip`.
+    pub const fn on_invalid_value(mut self, on_invalid_value: InvalidValue) -> Self {
+        self.config.on_invalid_value = on_invalid_value;
+        self
+    }
+
+    #[inline(always)]
+    ///Specifies initial capacity of buffer, into which response header value is formatted.
+    ///
+    ///It overrides generator's `IdGen::len_hint`, which allows to avoid re-allocation when ID's length is known (e.g. `36` for UUID).
+    pub const fn with_value_capacity(mut self, capacity: usize) -> Self {
+        self.config.value_capacity = Some(capacity);
+        self
+    }
+
+    #[inline(always)]
+    ///Specifies function to format response header value, instead of formatting it into newly allocated `BytesMut`.
+    ///
+    ///Function receives expected length of value (see `with_value_capacity`) and formatted ID, which is to be written into buffer of choice (e.g. thread local scratch buffer).
+    ///Returned bytes are subject to the same padding and validation as default buffer's content.
+    ///It has no effect on IDs, which are written without formatting (e.g. cached header value of generator).
+    pub const fn value_sink(mut self, sink: fn(usize, fmt::Arguments<'_>) -> bytes::Bytes) -> Self {
+        self.config.value_sink = Some(sink);
+        self
+    }
+
+    #[inline(always)]
+    ///Specifies minimal width of response header value, which is left-padded with zeros to reach it.
+    ///
+    ///This is intended for integer IDs (e.g. `42` with width 10 is written as `0000000042`), so that they can be sorted lexically.
+    ///Leading minus sign is kept in front of zeros (e.g. `-42` with width 5 is written as `-0042`), so that negative integers remain parseable.
+    ///Padding applies to textual representation of ID, including value returned by `value_sink` or `response_value`.
+    ///Incoming zero-padded integer is parsed as it is by means of `FromStr`.
+    pub const fn zero_pad(mut self, width: usize) -> Self {
+        self.config.pad_width = width;
+        self
+    }
+
+    #[inline(always)]
+    ///Accepts empty, or whitespace only, ID from client.
+    ///
+    ///By default such header is considered absent and ID is generated.
+    ///Note that it is still subject to parsing, which may reject empty value regardless.
+    pub const fn accept_empty_ids(mut self) -> Self {
+        self.config.parse.accept_empty = true;
+        self
+    }
+
+    #[inline(always)]
+    ///Disables trimming of leading and trailing whitespace of ID from client.
+    ///
+    ///By default ID is trimmed before parsing, as whitespace is often added by proxies.
+    pub const fn preserve_whitespace(mut self) -> Self {
+        self.config.parse.trim = false;
+        self
+    }
+
+    #[inline(always)]
+    ///Treats ID as structured field item (RFC 8941), both in request and response.
+    ///
+    ///Incoming value, regardless of its source, must be string (e.g. `"abc"`), token or integer, optionally followed by parameters, which are ignored.
+    ///String is unquoted before parsing, while value of any other structure is considered invalid, hence new ID is generated.
+    ///Outgoing ID is written as string, and it is skipped if it contains characters outside of visible ASCII range.
+    pub const fn structured_field(mut self) -> Self {
+        self.config.parse.structured_field = true;
+        self
+    }
+
+    #[inline]
+    ///Specifies query parameter to read ID from, when header is absent or invalid.
+    ///
+    ///Parameter's value is used as it is, without percent decoding, and it is subject to the same parsing as header.
+    pub fn query_param(mut self, name: &str) -> Self {
+        self.config.shared_mut().query_param = Some(name.into());
+        self
+    }
+
+    #[inline(always)]
+    ///Specifies function to transform ID after it is resolved, regardless whether it is taken from header or generated.
+    ///
+    ///Transformed ID is stored in request's extensions and written into response.
+    ///Note that ID re-used from request's extensions is not transformed.
+    pub const fn map_id(mut self, map_id: fn(O) -> O) -> Self {
+        self.hooks.map_id = Some(map_id);
+        self
+    }
+
+    #[inline(always)]
+    ///Specifies function to parse ID from client, instead of using its `FromStr` implementation.
+    ///
+    ///It is applied after trimming, and `None` means ID is invalid, hence new ID is generated.
+    pub const fn parse_with(mut self, parse: fn(&str) -> Option<O>) -> Self {
+        self.hooks.parse = Some(parse);
+        self
+    }
+
+    #[inline(always)]
+    ///Specifies function to derive response header's value from ID, instead of using its `Display` implementation.
+    ///
+    ///Request's extensions still contain full ID.
+    ///Derived value is subject to the same padding and validation as ID's textual representation.
+    pub const fn response_value(mut self, response_value: fn(&O) -> String) -> Self {
+        self.hooks.response_value = Some(response_value);
+        self
+    }
+
+    #[inline(always)]
+    ///Disables reuse of ID already present in request's extensions.
+    ///
+    ///By default, if request already has ID inserted by layer of this crate (e.g. when layer is nested), it is used as it is.
+    ///With this option, ID is resolved anew.
+    ///
+    ///Existing value is not overwritten, as it may be inserted by other middleware for different purpose.
+    ///Instead ID is inserted as `RequestId<O>`, unless `force_overwrite` is specified.
+    pub const fn overwrite_extension(mut self) -> Self {
+        self.config.reuse_extension = false;
+        self
+    }
+
+    #[inline(always)]
+    ///Re-uses any value of ID's type, present in request's extensions, as ID.
+    ///
+    ///By default only ID inserted by this crate (e.g. by outer layer or `set_request_id`) is re-used,
+    ///as value of the same type (e.g. `String`) may be inserted by unrelated middleware, which shouldn't be echoed in response.
+    ///This allows to accept ID, which is inserted by own middleware placed in front of this layer.
+    pub const fn reuse_any_extension(mut self) -> Self {
+        self.config.reuse_extension = true;
+        self.config.reuse_any_extension = true;
+        self
+    }
+
+    #[inline(always)]
+    ///Disables reuse of ID already present in request's extensions, and replaces it with resolved ID.
+    ///
+    ///Refer to `overwrite_extension` for details.
+    pub const fn force_overwrite(mut self) -> Self {
+        self.config.reuse_extension = false;
+        self.config.force_overwrite = true;
+        self
+    }
+
+    #[inline(always)]
+    ///Disables insertion of ID into request's extensions.
+    ///
+    ///ID is still written into response header, but it is not accessible to handlers.
+    ///ID already present in request's extensions is still re-used, unless `overwrite_extension` is specified.
+    pub const fn skip_request_extension(mut self) -> Self {
+        self.config.insert_extension = false;
+        self
+    }
+
+    #[inline(always)]
+    ///Disables writing of ID into response's headers.
+    ///
+    ///Unlike `response_header_when`, it is known before calling inner service, hence ID is moved into request's extensions without copying it.
+    pub const fn skip_response_header(mut self) -> Self {
+        self.config.response_header = false;
+        self
+    }
+
+    #[cfg(feature = "scope")]
+    #[inline(always)]
+    ///Makes ID accessible via `current_request_id` while inner service's future is polled.
+    ///
+    ///ID is set before each poll and cleared after it, hence it is available to any code executed within request's future.
+    pub const fn scope_id(mut self) -> Self {
+        self.config.scope_id = true;
+        self
+    }
+
+    #[inline(always)]
+    ///Specifies predicate on response's status to determine whether ID should be written into response's headers.
+    ///
+    ///Request's extensions always contain ID regardless of it.
+    ///By default header is always written.
+    ///
+    ///Note that responses stored by caching layers, which are placed in front of this middleware, may carry ID of the request they were originally produced for.
+    pub const fn response_header_when(mut self, predicate: fn(http::StatusCode) -> bool) -> Self {
+        self.config.response_header_when = predicate;
+        self
+    }
+
+    #[cfg(feature = "hash")]
+    #[inline]
+    ///Writes lower case hex of `SHA-256(salt || id)` into response's headers instead of ID itself.
+    ///
+    ///This allows to avoid reflecting client's ID, while request's extensions still contain original ID.
+    ///Hash is computed over ID's textual representation, and it takes precedence over `response_value`.
+    ///
+    ///Use `verify_response_hash` to check hash in constant time.
+    pub fn response_hash(mut self, salt: &[u8]) -> Self {
+        self.config.shared_mut().response_hash = Some(bytes::Bytes::copy_from_slice(salt));
+        self
+    }
+
+    #[inline(always)]
+    ///Writes ID into response's headers only when status is `2xx`.
+    pub const fn response_header_on_success_only(self) -> Self {
+        self.response_header_when(is_success)
+    }
+
+    #[inline(always)]
+    ///Writes ID into response's headers only when status is `4xx` or `5xx`.
+    pub const fn response_header_on_errors_only(self) -> Self {
+        self.response_header_when(is_error)
+    }
+
+    #[inline(always)]
+    ///Writes ID into response's headers unless status is `304 Not Modified`.
+    ///
+    ///Such response is usually produced from cached one, hence echoing per-request ID may leak into caches.
+    pub const fn skip_not_modified(self) -> Self {
+        self.response_header_when(is_modified)
+    }
+
+    #[inline]
+    ///Specifies additional response header, which value is rendered from `template`.
+    ///
+    ///First occurrence of `{id}` within `template` is replaced with the same value as written into main header
+    ///(e.g. `by=svcA;id={id}`).
+    ///If there is no placeholder, then value is appended to the end of `template`.
+    ///
+    ///Header is written only if main header is written and rendered value is valid.
+    pub fn structured_header(mut self, name: http::HeaderName, template: &str) -> Self {
+        self.config.shared_mut().structured_header = Some((name, utils::Template::new(template)));
+        self
+    }
+
+    #[inline]
+    ///Fallible version of `structured_header`, which parses header's `name`.
+    pub fn try_structured_header(self, name: &str, template: &str) -> Result<Self, RequestIdError> {
+        match http::HeaderName::from_bytes(name.as_bytes()) {
+            Ok(name) => Ok(self.structured_header(name, template)),
+            Err(_) => Err(RequestIdError::InvalidHeaderName),
+        }
+    }
+
+    #[inline]
+    ///Specifies methods for which ID is always generated, ignoring ID supplied by client.
+    ///
+    ///Requests with other methods use ID from header, if it is valid.
+    pub fn generate_for_methods(mut self, methods: &[Method]) -> Self {
+        self.config.shared_mut().generate_for_methods = methods.to_vec();
+        self
+    }
+
+    #[inline(always)]
+    ///Specifies function to determine policy of each request, before its ID is resolved.
+    ///
+    ///`IdPolicy::Generate` works the same way as `generate_for_methods` for particular request,
+    ///while `IdPolicy::Trust` leaves it to the rest of configuration.
+    ///`IdPolicy::Reject` responds immediately with body of `DefaultRejection`, unless replaced via `with_rejection`.
+    pub fn policy_fn(mut self, policy: fn(RequestRef<'_>) -> IdPolicy) -> GenerateRequestIdLayer<G, O, DefaultRejection> {
+        self.config.policy = Some(policy);
+        self.with_rejection()
+    }
+
+    #[inline(always)]
+    ///Rejects requests without ID header with specified `status` (e.g. `400 Bad Request`), instead of generating ID.
+    ///
+    ///This is intended for services behind gateway, which must always supply ID, so that its misconfiguration is detected early.
+    ///Header with invalid ID is still replaced with generated one, see `require_valid_header` to reject it too.
+    ///ID taken from other sources (e.g. `query_param`), as well as ID re-used from request's extensions, satisfies requirement.
+    ///Requests, which ID is always generated, are never rejected.
+    ///
+    ///Rejection response has no ID, and its body is specified by `DefaultRejection`, unless replaced via `with_rejection`.
+    pub fn require_header(mut self, status: http::StatusCode) -> GenerateRequestIdLayer<G, O, DefaultRejection> {
+        self.config.required_header = Some(RequiredHeader {
+            status,
+            reject_invalid: false,
+        });
+        self.with_rejection()
+    }
+
+    #[inline(always)]
+    ///Rejects requests without valid ID with specified `status`, instead of generating ID.
+    ///
+    ///Refer to `require_header` for details.
+    pub fn require_valid_header(mut self, status: http::StatusCode) -> GenerateRequestIdLayer<G, O, DefaultRejection> {
+        self.config.required_header = Some(RequiredHeader {
+            status,
+            reject_invalid: true,
+        });
+        self.with_rejection()
+    }
+
+    #[inline(always)]
+    ///Stacks layer of another ID (e.g. trace and span IDs) beneath this layer.
+    ///
+    ///This is shorthand for `tower_layer::Stack`, hence each ID is resolved by own middleware according to own configuration.
+    ///`other` is applied after this layer's ID is resolved, and its header is written into response first.
+    ///If both layers use the same header name, then this layer's ID takes precedence in response.
+    ///
+    ///Each ID is stored in request's extensions under own type.
+    ///If `O` and `NO` are the same type, then `other` re-uses this layer's ID, unless `overwrite_extension` is specified for `other`.
+    pub const fn stack_id<N, NO, NR>(self, other: GenerateRequestIdLayer<N, NO, NR>) -> tower_layer::Stack<GenerateRequestIdLayer<N, NO, NR>, Self> {
+        tower_layer::Stack::new(other, self)
+    }
+}
+
+impl<G, O: AlwaysValidHeader, R> GenerateRequestIdLayer<G, O, R> {
+    #[inline(always)]
+    ///Writes ID into response header without validating it, as its textual representation is always valid header value.
+    ///
+    ///Hence `on_invalid_value` has no effect.
+    pub const fn assume_valid_header(mut self) -> Self {
+        self.hooks.format = Some(valid::format_valid_header_value::<O>);
+        self
+    }
+}
+
+impl<G, O: core::str::FromStr> From<G> for GenerateRequestIdLayer<G, O> {
+    #[inline(always)]
+    fn from(gen: G) -> Self {
+        Self::new(gen)
+    }
+}
+
+impl<G: Default, O: core::str::FromStr> Default for GenerateRequestIdLayer<G, O> {
+    fn default() -> Self {
+        Self {
+            gen: Default::default(),
+            config: Config::default(),
+            hooks: Hooks::FROM_STR,
+            _rejection: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<G, O, R> GenerateRequestIdLayer<G, O, R> {
+    #[inline(always)]
+    ///Wraps errors of inner service into `ErrorWithId`, carrying ID of the failed request.
+    ///
+    ///This is shortcut to place `RequestIdOnErrorLayer` right inside of this layer, hence successful responses are unaffected.
+    ///ID is taken from request's extensions, therefore `ExtensionDisabled` error is returned when `skip_request_extension` is used.
+    pub fn map_error_with_id(self) -> Result<tower_layer::Stack<RequestIdOnErrorLayer<O>, Self>, RequestIdError> {
+        match self.config.insert_extension {
+            true => Ok(tower_layer::Stack::new(RequestIdOnErrorLayer::new(), self)),
+            false => Err(RequestIdError::ExtensionDisabled),
+        }
+    }
+}
+
+#[cfg(feature = "tower")]
+impl<G: IdGen<O> + Clone + Send + Sync + 'static, O: GenerateOnlyId<G> + Send + Sync + 'static, R: 'static> GenerateRequestIdLayer<G, O, R> {
+    ///Converts into type erased `BoxLayer`, suitable for dynamic assembly of middleware stacks.
+    pub fn into_boxed<S, ReqBody, ResBody>(self) -> tower::util::BoxLayer<S, Request<ReqBody>, Response<ResBody>, S::Error>
+    where
+        S: Service<Request<ReqBody>, Response = Response<ResBody>> + Send + 'static,
+        S::Future: Send + 'static,
+        ResBody: Send + 'static,
+        R: Rejection<ResBody>,
+    {
+        tower::util::BoxLayer::new(self)
+    }
+}
+
+impl<S, G: IdGen<O> + Clone, O: GenerateOnlyId<G>, R> Layer<S> for GenerateRequestIdLayer<G, O, R> {
+    type Service = GenerateRequestId<S, G, O, R>;
+
+    #[inline(always)]
+    fn layer(&self, inner: S) -> Self::Service {
+        GenerateRequestId::with_config(inner, self.gen.clone(), self.config.clone(), self.hooks)
+    }
+}
+
+#[derive(Debug)]
+///Service for adding request id.
+///
+///See module documentation for details.
+pub struct GenerateRequestId<S, G, O, R = NoRejection> {
+    inner: S,
+    gen: G,
+    config: Config,
+    hooks: Hooks<O>,
+    pregenerated: Option<O>,
+    connection_id: Option<O>,
+    _rejection: PhantomData<fn() -> R>,
+}
+
+impl<S, G, O: core::str::FromStr> GenerateRequestId<S, G, O> {
+    #[inline(always)]
+    ///Creates new instance
+    pub const fn new(inner: S, gen: G) -> Self {
+        Self::with_config(inner, gen, Config::DEFAULT, Hooks::FROM_STR)
+    }
+}
+
+impl<S, G, O: FromHeaderStr> GenerateRequestId<S, G, O> {
+    #[inline(always)]
+    ///Creates new instance, which converts ID from client by means of `FromHeaderStr`.
+    pub const fn new_infallible(inner: S, gen: G) -> Self {
+        Self::with_config(inner, gen, Config::DEFAULT, Hooks::FROM_HEADER_STR)
+    }
+}
+
+impl<S, G, O, R> GenerateRequestId<S, G, O, R> {
+    #[inline(always)]
+    const fn with_config(inner: S, gen: G, config: Config, hooks: Hooks<O>) -> Self {
+        Self {
+            inner,
+            gen,
+            config,
+            hooks,
+            pregenerated: None,
+            connection_id: None,
+            _rejection: PhantomData,
+        }
+    }
+
+    #[inline]
+    ///Returns number of IDs, generated by this service and its clones.
+    ///
+    ///Returns `None` unless `count_generated` is enabled on the layer.
+    pub fn generated_count(&self) -> Option<u64> {
+        self.config.generated_count()
+    }
+}
+
+impl<S: Clone, G: Clone, O, R> Clone for GenerateRequestId<S, G, O, R> {
+    #[inline]
+    fn clone(&self) -> Self {
+        //Pre-generated and connection IDs belong to the particular instance, so clone has to generate its own.
+        Self::with_config(self.inner.clone(), self.gen.clone(), self.config.clone(), self.hooks)
+    }
+}
+
+impl<S, G: IdGen<O>, O: GenerateOnlyId<G>, R> GenerateRequestId<S, G, O, R> {
+    //Returns resolved ID, and client's ID, when it is kept as parent of generated one.
+    fn resolve_id<B>(&mut self, req: &Request<B>, generate: bool) -> Result<(O, Option<O>), http::StatusCode> {
+        let parse = match self.hooks.parse {
+            Some(_) if generate || self.config.shared().generate_for_methods.contains(req.method()) => None,
+            parse => parse,
+        };
+
+        if self.config.per_connection {
+            //Client's ID is not used, but requirement still applies to every request.
+            if let (Some(_), Some(parse)) = (self.config.required_header, parse) {
+                self.check_required(req, self.client_id(req, parse).is_some())?;
+            }
+
+            if let Some(id) = &self.connection_id {
+                return Ok((id.clone(), None));
+            }
+
+            let id = self.generate(req, true)?;
+            let id = self.map_id(id);
+            self.connection_id = Some(id.clone());
+            return Ok((id, None));
+        }
+
+        let id = match parse {
+            None => None,
+            Some(parse) => self.client_id(req, parse),
+        };
+
+        #[cfg(feature = "metrics")]
+        if id.is_some() {
+            metrics::counter!(ACCEPTED_COUNTER).increment(1);
+        }
+
+        let (id, parent) = match id {
+            //Client supplied ID, hence generation is not limited
+            Some(id) if self.config.derive_child => (self.generate_unlimited(req), Some(id)),
+            Some(id) => (id, None),
+            None => {
+                if parse.is_some() {
+                    self.check_required(req, false)?;
+                }
+
+                (self.generate(req, true)?, None)
+            },
+        };
+
+        Ok((self.map_id(id), parent))
+    }
+
+    //Looks up ID supplied by client within all configured sources.
+    fn client_id<B>(&self, req: &Request<B>, parse: fn(&str) -> Option<O>) -> Option<O> {
+        match parse_headers(req.headers(), &self.config.header_name, self.config.parse, parse) {
+            Some(id) => Some(id),
+            None => match self.config.grpc_bin_header.as_ref().and_then(|name| parse_bin_headers(req.headers(), name, self.config.parse, parse)) {
+                Some(id) => Some(id),
+                None => match &self.config.shared().query_param {
+                    Some(name) => parse_query(req.uri(), name, self.config.parse, parse),
+                    None => None,
+                },
+            },
+        }
+    }
+
+    //Rejects request according to `required_header`, unless client supplied valid ID.
+    fn check_required<B>(&self, req: &Request<B>, is_valid: bool) -> Result<(), http::StatusCode> {
+        match self.config.required_header {
+            Some(required) if !is_valid && (required.reject_invalid || !req.headers().contains_key(&self.config.header_name)) => Err(required.status),
+            _ => Ok(()),
+        }
+    }
+
+    //Generates new ID, taking token out of `generation_limit` only when `limited`.
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    fn generate<B>(&mut self, req: &Request<B>, limited: bool) -> Result<O, http::StatusCode> {
+        #[cfg(feature = "std")]
+        if let (Some(limit), true) = (&self.config.shared().generation_limit, limited) {
+            if !limit.try_acquire() {
+                return Err(http::StatusCode::TOO_MANY_REQUESTS);
+            }
+        }
+        Ok(self.generate_unlimited(req))
+    }
+
+    fn generate_unlimited<B>(&mut self, req: &Request<B>) -> O {
+        if let Some(generated) = &self.config.shared().generated {
+            generated.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+        }
+        #[cfg(feature = "metrics")]
+        metrics::counter!(GENERATED_COUNTER).increment(1);
+        match self.pregenerated.take() {
+            Some(id) => id,
+            None => self.gen.gen_for(RequestRef::new(req)),
+        }
+    }
+
+    #[inline]
+    fn map_id(&self, id: O) -> O {
+        match self.hooks.map_id {
+            Some(map_id) => map_id(id),
+            None => id,
+        }
+    }
+}
+
+//use separate type parameter for request and response bodies.
+//to make sure user is free to use whatever handler he wishes.
+impl<ReqBody, ResBody, S: Service<Request<ReqBody>, Response = Response<ResBody>>, O: GenerateOnlyId<G> + Send + Sync + 'static, G: IdGen<O> + Clone + Send + Sync + 'static, R: Rejection<ResBody>> Service<Request<ReqBody>> for GenerateRequestId<S, G, O, R> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFut<S::Future, O, ResBody>;
+
+    #[inline]
+    fn poll_ready(&mut self, ctx: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+        if self.config.enabled && self.gen.poll_ready(ctx).is_pending() {
+            return task::Poll::Pending;
+        }
+
+        let result = self.inner.poll_ready(ctx);
+        if self.config.pregenerate && self.pregenerated.is_none() {
+            if let task::Poll::Ready(Ok(())) = result {
+                self.pregenerated = Some(self.gen.gen());
+            }
+        }
+        result
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        if !self.config.enabled {
+            return ResponseFut::inner(self.inner.call(req), ResponseState {
+                id: None,
+                header_value: None,
+                header_value_fn: None,
+                response_value: None,
+                format: None,
+                write_id: None,
+                len_hint: 0,
+                value_sink: None,
+                pad_width: 0,
+                on_invalid_value: self.config.on_invalid_value,
+                structured_header: None,
+                structured_field: false,
+                response_header_when: self.config.response_header_when,
+                header_name: HEADER,
+                #[cfg(feature = "scope")]
+                scope_id: None,
+                #[cfg(feature = "hash")]
+                response_hash: None,
+            });
+        }
+
+        let generate = match self.config.policy.map(|policy| policy(RequestRef::new(&req))) {
+            Some(IdPolicy::Reject(status)) => match R::body(status) {
+                Some(body) => return reject(status, body),
+                None => false,
+            },
+            Some(IdPolicy::Generate) => true,
+            Some(IdPolicy::Trust) | None => false,
+        };
+
+        let response_header = self.config.response_header;
+        let options = InsertOptions {
+            reuse_extension: self.config.reuse_extension,
+            reuse_any_extension: self.config.reuse_any_extension,
+            insert_extension: self.config.insert_extension,
+            force_overwrite: self.config.force_overwrite,
+            store_string: self.config.store_string,
+            #[cfg(feature = "scope")]
+            scope_id: self.config.scope_id,
+            keep_id: response_header,
+        };
+        let inserted = insert_request_id(&mut req, options, |req| match self.resolve_id(req, generate) {
+            Ok((id, parent)) => {
+                if let Some(parent) = parent {
+                    req.extensions_mut().insert(ParentId(parent));
+                }
+                Ok(id)
+            },
+            Err(status) => match R::body(status) {
+                Some(body) => Err((status, body)),
+                //Request cannot be rejected, hence ID is generated as if rejection is not configured.
+                None => {
+                    let id = self.generate_unlimited(req);
+                    Ok(self.map_id(id))
+                },
+            },
+        });
+        let inserted = match inserted {
+            Ok(inserted) => inserted,
+            Err((status, body)) => return reject(status, body),
+        };
+
+        #[cfg(feature = "hash")]
+        let is_hashed = self.config.shared().response_hash.is_some();
+        #[cfg(not(feature = "hash"))]
+        let is_hashed = false;
+        let (header_value, header_value_fn) = match &inserted.id {
+            Some(id) if !(self.hooks.response_value.is_some() || is_hashed) => (self.gen.header_value(id), self.gen.header_value_fn()),
+            _ => (None, None),
+        };
+
+        ResponseFut::inner(self.inner.call(req), ResponseState {
+            header_value,
+            header_value_fn,
+            response_value: self.hooks.response_value,
+            format: self.hooks.format,
+            write_id: self.gen.write_id(),
+            id: inserted.id,
+            len_hint: core::cmp::max(self.config.value_capacity.unwrap_or_else(|| self.gen.len_hint()), self.config.pad_width),
+            value_sink: self.config.value_sink,
+            pad_width: self.config.pad_width,
+            on_invalid_value: self.config.on_invalid_value,
+            structured_header: self.config.shared().structured_header.clone(),
+            structured_field: self.config.parse.structured_field,
+            response_header_when: self.config.response_header_when,
+            header_name: self.config.response_header_name.clone(),
+            #[cfg(feature = "scope")]
+            scope_id: inserted.scope_id,
+            #[cfg(feature = "hash")]
+            response_hash: self.config.shared().response_hash.clone(),
+        })
+    }
+}
+
+//Creates future, which resolves to rejection response with `status`.
+fn reject<F, T, B>(status: http::StatusCode, body: B) -> ResponseFut<F, T, B> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    ResponseFut::immediate(response)
+}
+
+fn format_header_value<T: fmt::Display>(id: &T, len_hint: usize, pad_width: usize, on_invalid_value: InvalidValue) -> Option<http::HeaderValue> {
+    sink_header_value(None, id, len_hint, pad_width, on_invalid_value)
+}
+
+fn sink_header_value<T: fmt::Display>(sink: Option<ValueSinkFn>, id: &T, len_hint: usize, pad_width: usize, on_invalid_value: InvalidValue) -> Option<http::HeaderValue> {
+    let args = format_args!("{}", id);
+    let header_value = match sink {
+        Some(sink) => utils::zero_pad(sink(len_hint, args), pad_width),
+        None => {
+            let mut header_value = utils::BytesWriter::with_capacity(len_hint);
+            //Retarded implementation could fail intentionally, but there is no reason for proper one to fail when writing into Vec.
+            let _ = fmt::Write::write_fmt(&mut header_value, args);
+            header_value.zero_pad(pad_width);
+            header_value.freeze()
+        },
+    };
+
+    validate_header_value(header_value, on_invalid_value)
+}
+
+//Adapter of `WriteIdFn` to `Display`, so that it can be written into value sink.
+struct WriteId<'a, T>(&'a T, WriteIdFn<T>);
+
+impl<T> fmt::Display for WriteId<'_, T> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.1)(self.0, fmt)
+    }
+}
+
+fn write_header_value<T>(sink: Option<ValueSinkFn>, id: &T, write_id: WriteIdFn<T>, len_hint: usize, pad_width: usize, on_invalid_value: InvalidValue) -> Option<http::HeaderValue> {
+    let header_value = match sink {
+        Some(sink) => utils::zero_pad(sink(len_hint, format_args!("{}", WriteId(id, write_id))), pad_width),
+        None => {
+            let mut header_value = utils::BytesWriter::with_capacity(len_hint);
+            let _ = write_id(id, &mut header_value);
+            header_value.zero_pad(pad_width);
+            header_value.freeze()
+        },
+    };
+
+    validate_header_value(header_value, on_invalid_value)
+}
+
+fn validate_header_value(mut header_value: bytes::Bytes, on_invalid_value: InvalidValue) -> Option<http::HeaderValue> {
+    if !utils::is_safe_value(&header_value) {
+        match on_invalid_value {
+            InvalidValue::Skip => return None,
+            InvalidValue::Encode => header_value = utils::percent_encode(&header_value),
+        }
+    }
+
+    http::HeaderValue::from_maybe_shared(header_value).ok()
+}
+
+//State necessary to write ID into response.
+struct ResponseState<T> {
+    id: Option<T>,
+    header_value: Option<http::HeaderValue>,
+    header_value_fn: Option<HeaderValueFn<T>>,
+    response_value: Option<fn(&T) -> String>,
+    format: Option<FormatFn<T>>,
+    write_id: Option<WriteIdFn<T>>,
+    len_hint: usize,
+    value_sink: Option<ValueSinkFn>,
+    pad_width: usize,
+    on_invalid_value: InvalidValue,
+    structured_header: Option<(http::HeaderName, utils::Template)>,
+    structured_field: bool,
+    response_header_when: fn(http::StatusCode) -> bool,
+    header_name: http::HeaderName,
+    #[cfg(feature = "scope")]
+    scope_id: scope::ScopedId,
+    #[cfg(feature = "hash")]
+    response_hash: Option<bytes::Bytes>,
+}
+
+impl<T: fmt::Display> ResponseState<T> {
+    fn write<B>(&mut self, resp: &mut Response<B>) {
+        let id = match self.id.take() {
+            Some(id) if (self.response_header_when)(resp.status()) => id,
+            _ => return,
+        };
+
+        #[cfg(feature = "hash")]
+        let header_value = match self.response_hash.take() {
+            Some(salt) => Some(hash::header_value(&salt, &id)),
+            None => self.header_value.take(),
+        };
+        #[cfg(not(feature = "hash"))]
+        let header_value = self.header_value.take();
+
+        let header_value = match header_value.or_else(|| self.header_value_fn.and_then(|header_value_fn| header_value_fn(&id))) {
+            Some(header_value) => header_value,
+            None => {
+                let header_value = match self.response_value {
+                    Some(response_value) => sink_header_value(self.value_sink, &response_value(&id), self.len_hint, self.pad_width, self.on_invalid_value),
+                    None => match self.format {
+                        Some(format) => format(&id, self.len_hint, self.pad_width, self.on_invalid_value),
+                        None => match self.write_id {
+                            Some(write_id) => write_header_value(self.value_sink, &id, write_id, self.len_hint, self.pad_width, self.on_invalid_value),
+                            None => sink_header_value(self.value_sink, &id, self.len_hint, self.pad_width, self.on_invalid_value),
+                        },
+                    },
+                };
+                match header_value {
+                    Some(header_value) => header_value,
+                    None => return,
+                }
+            },
+        };
+
+        if let Some((name, template)) = self.structured_header.take() {
+            if let Ok(value) = http::HeaderValue::from_maybe_shared(template.render(header_value.as_bytes())) {
+                resp.headers_mut().insert(name, value);
+            }
+        }
+        let header_value = match self.structured_field {
+            true => match utils::sf_string(header_value.as_bytes()).and_then(|value| http::HeaderValue::from_maybe_shared(value).ok()) {
+                Some(header_value) => header_value,
+                None => return,
+            },
+            false => header_value,
+        };
+        resp.headers_mut().insert(core::mem::replace(&mut self.header_name, HEADER), header_value);
+    }
+}
+
+pin_project_lite::pin_project! {
+    #[project = ResponseFutKindProj]
+    enum ResponseFutKind<F, T, B> {
+        Inner {
+            #[pin]
+            inner: F,
+            state: ResponseState<T>,
+        },
+        Immediate {
+            response: Option<Response<B>>,
+        },
+    }
+}
+
+pin_project_lite::pin_project! {
+    ///Future adding request-id to list of response's headers.
+    ///
+    ///It never fails on its own: if ID cannot be written as header value, response is returned unmodified.
+    ///Header already present in response is replaced, including all its values.
+    ///
+    ///Alternatively it can resolve to pre-built response, without calling inner service at all.
+    pub struct ResponseFut<F, T, B> {
+        #[pin]
+        kind: ResponseFutKind<F, T, B>,
+    }
+}
+
+impl<F, T, B> ResponseFut<F, T, B> {
+    #[inline(always)]
+    fn inner(inner: F, state: ResponseState<T>) -> Self {
+        Self {
+            kind: ResponseFutKind::Inner {
+                inner,
+                state,
+            },
+        }
+    }
+
+    #[inline(always)]
+    ///Creates future, which resolves to `response` as it is, without polling any inner service.
+    pub fn immediate(response: Response<B>) -> Self {
+        Self {
+            kind: ResponseFutKind::Immediate {
+                response: Some(response),
+            },
+        }
+    }
+}
+
+impl<B, E, F: Future<Output = Result<Response<B>, E>>, T: fmt::Display> Future for ResponseFut<F, T, B> {
+    type Output = F::Output;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+        match self.project().kind.project() {
+            ResponseFutKindProj::Inner { inner, state } => {
+                let resp = {
+                    #[cfg(feature = "scope")]
+                    let _scope = match state.scope_id.is_some() {
+                        true => Some(scope::ScopeGuard::enter(&mut state.scope_id)),
+                        false => None,
+                    };
+                    Future::poll(inner, ctx)
+                };
+
+                let mut resp = match resp {
+                    task::Poll::Ready(resp) => resp?,
+                    task::Poll::Pending => return task::Poll::Pending,
+                };
+
+                state.write(&mut resp);
+                task::Poll::Ready(Ok(resp))
+            },
+            ResponseFutKindProj::Immediate { response } => match response.take() {
+                Some(response) => task::Poll::Ready(Ok(response)),
+                None => panic!("ResponseFut polled after completion"),
+            },
+        }
     }
 }
@@ -0,0 +1,45 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug)]
+//Token bucket, which is refilled continuously with `rate` tokens per second up to `rate` tokens.
+pub struct TokenBucket {
+    rate: f64,
+    state: Mutex<State>,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            rate: rate as f64,
+            state: Mutex::new(State {
+                tokens: rate as f64,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    //Takes single token, returning `false` if bucket is empty.
+    pub fn try_acquire(&self) -> bool {
+        //State is always consistent, hence poisoning can be ignored.
+        let mut state = self.state.lock().unwrap_or_else(|error| error.into_inner());
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(state.updated).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.rate);
+        state.updated = now;
+
+        match state.tokens >= 1.0 {
+            true => {
+                state.tokens -= 1.0;
+                true
+            },
+            false => false,
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use core::{fmt, task};
+use core::pin::Pin;
+use core::future::Future;
+use core::marker::PhantomData;
+
+use http::Request;
+use tower_layer::Layer;
+use tower_service::Service;
+
+///Error of inner service, carrying ID of the request it failed to process.
+///
+///It is produced by `RequestIdOnErrorLayer`, allowing outer layers to attach ID to response built out of error.
+pub struct ErrorWithId<E, O> {
+    id: Option<O>,
+    error: E,
+}
+
+impl<E, O> ErrorWithId<E, O> {
+    #[inline(always)]
+    ///Returns ID of the request, if it was present in request's extensions.
+    pub fn id(&self) -> Option<&O> {
+        self.id.as_ref()
+    }
+
+    #[inline(always)]
+    ///Returns error of inner service.
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    #[inline(always)]
+    ///Returns error of inner service, dropping ID.
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+
+    #[inline(always)]
+    ///Returns ID and error of inner service.
+    pub fn into_parts(self) -> (Option<O>, E) {
+        (self.id, self.error)
+    }
+}
+
+impl<E: fmt::Debug, O> fmt::Debug for ErrorWithId<E, O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //ID is redacted, like in `RequestId`, to avoid leaking it via logs
+        let id = self.id.as_ref().map(|_| format_args!("***"));
+        fmt.debug_struct("ErrorWithId").field("id", &id).field("error", &self.error).finish()
+    }
+}
+
+impl<E: fmt::Display, O> fmt::Display for ErrorWithId<E, O> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, fmt)
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display, O> std::error::Error for ErrorWithId<E, O> {
+}
+
+///Layer for attaching request id to inner service's errors.
+///
+///When inner service fails (e.g. `tower::timeout::Timeout` elapses), there is no response to write ID into.
+///This layer wraps error into `ErrorWithId`, so that layer handling errors can recover ID.
+///
+///It must be placed inside of `GenerateRequestIdLayer`, as ID of type `O` is taken from request's extensions, which is done by `GenerateRequestIdLayer::map_error_with_id`.
+///Note that if errors are converted into responses inside of `GenerateRequestIdLayer`, ID is written into such responses as usual, hence this layer is only needed when errors are handled by outer layers:
+///
+///```rust
+///use tower_http_req_id::{GenerateRequestIdLayer, RequestIdOnErrorLayer, StaticGenerator, StaticId};
+///
+///let layer = tower_layer::Stack::new(
+///    //Inner layer, where timeout or other fallible middleware is placed
+///    RequestIdOnErrorLayer::<StaticId>::new(),
+///    //Outer layer
+///    GenerateRequestIdLayer::<_, StaticId>::new(StaticGenerator::new("id")),
+///);
+///```
+pub struct RequestIdOnErrorLayer<O> {
+    _id: PhantomData<fn() -> O>,
+}
+
+impl<O> RequestIdOnErrorLayer<O> {
+    #[inline(always)]
+    ///Creates new instance
+    pub const fn new() -> Self {
+        Self {
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<O> Default for RequestIdOnErrorLayer<O> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O> Clone for RequestIdOnErrorLayer<O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<O> fmt::Debug for RequestIdOnErrorLayer<O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RequestIdOnErrorLayer").finish()
+    }
+}
+
+impl<S, O> Layer<S> for RequestIdOnErrorLayer<O> {
+    type Service = RequestIdOnError<S, O>;
+
+    #[inline(always)]
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdOnError {
+            inner,
+            _id: PhantomData,
+        }
+    }
+}
+
+///Service for attaching request id to inner service's errors.
+///
+///See `RequestIdOnErrorLayer` for details.
+pub struct RequestIdOnError<S, O> {
+    inner: S,
+    _id: PhantomData<fn() -> O>,
+}
+
+impl<S: Clone, O> Clone for RequestIdOnError<S, O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, O> fmt::Debug for RequestIdOnError<S, O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RequestIdOnError").field("inner", &self.inner).finish()
+    }
+}
+
+impl<ReqBody, S: Service<Request<ReqBody>>, O: Clone + Send + Sync + 'static> Service<Request<ReqBody>> for RequestIdOnError<S, O> {
+    type Response = S::Response;
+    type Error = ErrorWithId<S::Error, O>;
+    type Future = RequestIdOnErrorFut<S::Future, O>;
+
+    #[inline]
+    fn poll_ready(&mut self, ctx: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(ctx).map_err(|error| ErrorWithId {
+            id: None,
+            error,
+        })
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let id = req.extensions().get::<O>().cloned();
+
+        RequestIdOnErrorFut {
+            inner: self.inner.call(req),
+            id,
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    ///Future attaching request id to inner service's error.
+    pub struct RequestIdOnErrorFut<F, O> {
+        #[pin]
+        inner: F,
+        id: Option<O>,
+    }
+}
+
+impl<R, E, F: Future<Output = Result<R, E>>, O> Future for RequestIdOnErrorFut<F, O> {
+    type Output = Result<R, ErrorWithId<E, O>>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+        let this = self.project();
+
+        match Future::poll(this.inner, ctx) {
+            task::Poll::Ready(Ok(resp)) => task::Poll::Ready(Ok(resp)),
+            task::Poll::Ready(Err(error)) => task::Poll::Ready(Err(ErrorWithId {
+                id: this.id.take(),
+                error,
+            })),
+            task::Poll::Pending => task::Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use core::{fmt, task};
+use core::marker::PhantomData;
+
+use http::Request;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{HEADER, DEFAULT_LEN_HINT, InvalidValue, format_header_value};
+
+///Layer for propagating request id to outgoing requests.
+///
+///It is client side counterpart of `GenerateRequestIdLayer`.
+///ID of type `O` is taken from outgoing request's extensions and written into its headers.
+///If request already has header, then it is left as it is.
+pub struct PropagateRequestIdLayer<O> {
+    header_name: http::HeaderName,
+    _id: PhantomData<fn() -> O>,
+}
+
+impl<O> PropagateRequestIdLayer<O> {
+    #[inline(always)]
+    ///Creates new instance
+    pub const fn new() -> Self {
+        Self {
+            header_name: HEADER,
+            _id: PhantomData,
+        }
+    }
+
+    #[inline]
+    ///Specifies header name to write ID into.
+    ///
+    ///Defaults to `x-request-id`.
+    pub fn header_name(mut self, name: http::HeaderName) -> Self {
+        self.header_name = name;
+        self
+    }
+}
+
+impl<O> Default for PropagateRequestIdLayer<O> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O> Clone for PropagateRequestIdLayer<O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            header_name: self.header_name.clone(),
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<O> fmt::Debug for PropagateRequestIdLayer<O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("PropagateRequestIdLayer").field("header_name", &self.header_name).finish()
+    }
+}
+
+impl<S, O> Layer<S> for PropagateRequestIdLayer<O> {
+    type Service = PropagateRequestId<S, O>;
+
+    #[inline(always)]
+    fn layer(&self, inner: S) -> Self::Service {
+        PropagateRequestId {
+            inner,
+            header_name: self.header_name.clone(),
+            _id: PhantomData,
+        }
+    }
+}
+
+///Service for propagating request id to outgoing requests.
+///
+///See `PropagateRequestIdLayer` for details.
+pub struct PropagateRequestId<S, O> {
+    inner: S,
+    header_name: http::HeaderName,
+    _id: PhantomData<fn() -> O>,
+}
+
+impl<S: Clone, O> Clone for PropagateRequestId<S, O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            header_name: self.header_name.clone(),
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, O> fmt::Debug for PropagateRequestId<S, O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("PropagateRequestId").field("inner", &self.inner).field("header_name", &self.header_name).finish()
+    }
+}
+
+impl<ReqBody, S: Service<Request<ReqBody>>, O: fmt::Display + Send + Sync + 'static> Service<Request<ReqBody>> for PropagateRequestId<S, O> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, ctx: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(ctx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        if !req.headers().contains_key(&self.header_name) {
+            let value = req.extensions().get::<O>().and_then(|id| format_header_value(id, DEFAULT_LEN_HINT, 0, InvalidValue::Skip));
+            if let Some(value) = value {
+                req.headers_mut().insert(self.header_name.clone(), value);
+            }
+        }
+
+        self.inner.call(req)
+    }
+}
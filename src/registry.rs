@@ -0,0 +1,92 @@
+use core::fmt;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use crate::{IdGen, GenerateRequestIdLayer};
+
+///Type-erased generator, stored within `GeneratorRegistry`.
+pub type DynIdGen<O> = Arc<dyn IdGen<O> + Send + Sync>;
+
+///Registry of type-erased generators, selected by name at runtime.
+///
+///This allows plugins to register own generators, while application selects one via configuration.
+///
+///```rust
+///use tower_http_req_id::{GeneratorRegistry, StaticGenerator, StaticId};
+///
+///let mut registry = GeneratorRegistry::<StaticId>::new();
+///registry.register("static", StaticGenerator::new("id"));
+///
+///let layer = registry.layer("static").expect("generator to be registered");
+///assert!(registry.layer("unknown").is_none());
+///```
+pub struct GeneratorRegistry<O = String> {
+    generators: BTreeMap<String, DynIdGen<O>>,
+}
+
+impl<O> GeneratorRegistry<O> {
+    #[inline(always)]
+    ///Creates empty registry.
+    pub const fn new() -> Self {
+        Self {
+            generators: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    ///Registers generator under `name`, returning previously registered generator, if any.
+    pub fn register<G: IdGen<O> + Send + Sync + 'static>(&mut self, name: &str, gen: G) -> Option<DynIdGen<O>> {
+        self.register_dyn(name, Arc::new(gen))
+    }
+
+    #[inline]
+    ///Registers already type-erased generator under `name`, returning previously registered generator, if any.
+    pub fn register_dyn(&mut self, name: &str, gen: DynIdGen<O>) -> Option<DynIdGen<O>> {
+        self.generators.insert(name.into(), gen)
+    }
+
+    #[inline]
+    ///Returns generator registered under `name`.
+    pub fn get(&self, name: &str) -> Option<DynIdGen<O>> {
+        self.generators.get(name).cloned()
+    }
+
+    #[inline]
+    ///Returns iterator over names of registered generators in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.generators.keys().map(String::as_str)
+    }
+}
+
+impl<O: core::str::FromStr> GeneratorRegistry<O> {
+    #[inline]
+    ///Creates layer out of generator registered under `name`.
+    ///
+    ///Returns `None` if there is no such generator.
+    pub fn layer(&self, name: &str) -> Option<GenerateRequestIdLayer<DynIdGen<O>, O>> {
+        self.get(name).map(GenerateRequestIdLayer::new)
+    }
+}
+
+impl<O> Default for GeneratorRegistry<O> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O> Clone for GeneratorRegistry<O> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            generators: self.generators.clone(),
+        }
+    }
+}
+
+impl<O> fmt::Debug for GeneratorRegistry<O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_set().entries(self.names()).finish()
+    }
+}
@@ -0,0 +1,55 @@
+use http::{Extensions, HeaderMap, Method, Request, Uri, Version};
+
+#[derive(Clone, Copy, Debug)]
+///Borrowed view of request's parts, available to generator.
+pub struct RequestRef<'a> {
+    method: &'a Method,
+    uri: &'a Uri,
+    version: Version,
+    headers: &'a HeaderMap,
+    extensions: &'a Extensions,
+}
+
+impl<'a> RequestRef<'a> {
+    #[inline(always)]
+    ///Creates new instance from request.
+    pub fn new<B>(req: &'a Request<B>) -> Self {
+        Self {
+            method: req.method(),
+            uri: req.uri(),
+            version: req.version(),
+            headers: req.headers(),
+            extensions: req.extensions(),
+        }
+    }
+
+    #[inline(always)]
+    ///Returns request's method.
+    pub fn method(&self) -> &'a Method {
+        self.method
+    }
+
+    #[inline(always)]
+    ///Returns request's URI.
+    pub fn uri(&self) -> &'a Uri {
+        self.uri
+    }
+
+    #[inline(always)]
+    ///Returns request's HTTP version.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    #[inline(always)]
+    ///Returns request's headers.
+    pub fn headers(&self) -> &'a HeaderMap {
+        self.headers
+    }
+
+    #[inline(always)]
+    ///Returns request's extensions.
+    pub fn extensions(&self) -> &'a Extensions {
+        self.extensions
+    }
+}
@@ -0,0 +1,190 @@
+use core::{fmt, ops, str, task};
+use alloc::string::String;
+
+use crate::{IdGen, RequestRef};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+///Wrapper of ID, which is stored in request's extensions when value of ID's type is already present there.
+///
+///This avoids overwriting value inserted by other middleware, which uses the same type for different purposes.
+///
+///`Debug` implementation doesn't reveal ID, as it may be secret, while `Display` writes ID as it is.
+///
+///It can be used as ID's type itself, in order to store IDs resolved by differently configured layers in request's extensions.
+///By default every layer, using the same ID's type `T`, stores ID under the same type, and inner layer's ID takes precedence.
+///Distinct `TAG` makes distinct type, hence each layer's ID is accessible via own `RequestId<T, TAG>`.
+///Generator of such ID is created via `TaggedGenerator`.
+pub struct RequestId<T, const TAG: u8 = 0>(pub T);
+
+impl<T, const TAG: u8> RequestId<T, TAG> {
+    #[inline(always)]
+    ///Returns underlying ID.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T, const TAG: u8> ops::Deref for RequestId<T, TAG> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const TAG: u8> fmt::Debug for RequestId<T, TAG> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("RequestId(***)")
+    }
+}
+
+impl<T: fmt::Display, const TAG: u8> fmt::Display for RequestId<T, TAG> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+///Textual representation of ID, stored in request's extensions when `GenerateRequestIdLayer::also_store_string` is enabled.
+///
+///Its type doesn't depend on ID's type, hence it can be accessed by code, which doesn't know ID's type.
+///Like `RequestId`, `Debug` implementation doesn't reveal ID.
+pub struct RequestIdStr(pub String);
+
+impl RequestIdStr {
+    #[inline(always)]
+    ///Returns underlying string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    #[inline(always)]
+    ///Returns underlying string.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl ops::Deref for RequestIdStr {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Debug for RequestIdStr {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("RequestIdStr(***)")
+    }
+}
+
+impl fmt::Display for RequestIdStr {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+///ID provided by client, which is kept as parent of generated ID.
+///
+///It is stored in request's extensions, when `GenerateRequestIdLayer::derive_child` is enabled.
+///Like `RequestId`, `Debug` implementation doesn't reveal ID.
+pub struct ParentId<T>(pub T);
+
+impl<T> ParentId<T> {
+    #[inline(always)]
+    ///Returns underlying ID.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for ParentId<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> fmt::Debug for ParentId<T> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("ParentId(***)")
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for ParentId<T> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
+    }
+}
+
+impl<T: str::FromStr, const TAG: u8> str::FromStr for RequestId<T, TAG> {
+    type Err = T::Err;
+
+    #[inline(always)]
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        T::from_str(text).map(Self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Adapter of generator of `T`, producing `RequestId<T, TAG>`.
+///
+///```rust
+///use tower_http_req_id::{GenerateRequestIdLayer, RequestId, StaticGenerator, StaticId, TaggedGenerator};
+///
+///const UPSTREAM: u8 = 1;
+///
+///let layer = GenerateRequestIdLayer::<_, RequestId<StaticId, UPSTREAM>>::new(TaggedGenerator::<_, UPSTREAM>::new(StaticGenerator::new("id")));
+///```
+pub struct TaggedGenerator<G, const TAG: u8> {
+    inner: G,
+}
+
+impl<G, const TAG: u8> TaggedGenerator<G, TAG> {
+    #[inline(always)]
+    ///Creates new instance.
+    pub const fn new(inner: G) -> Self {
+        Self {
+            inner,
+        }
+    }
+}
+
+impl<T, G: IdGen<T>, const TAG: u8> IdGen<RequestId<T, TAG>> for TaggedGenerator<G, TAG> {
+    #[inline(always)]
+    fn gen(&self) -> RequestId<T, TAG> {
+        RequestId(self.inner.gen())
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> RequestId<T, TAG> {
+        RequestId(self.inner.gen_for(req))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.inner.len_hint()
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &RequestId<T, TAG>) -> Option<http::HeaderValue> {
+        self.inner.header_value(&id.0)
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        self.inner.poll_ready(ctx)
+    }
+}
@@ -0,0 +1,118 @@
+use core::{fmt, task};
+
+use crate::{IdGen, RequestRef};
+
+#[derive(Clone, Copy)]
+///Generator, which re-generates ID of inner generator until it satisfies predicate `P`.
+///
+///Generation is attempted at most `max_attempts` times, and the last ID is returned even if it doesn't satisfy predicate.
+///This is useful to filter out rare undesirable IDs (e.g. random token consisting only of digits).
+pub struct RetryGen<G, P> {
+    inner: G,
+    predicate: P,
+    max_attempts: usize,
+}
+
+impl<G, P> RetryGen<G, P> {
+    #[inline(always)]
+    ///Creates new instance, which generates ID at most `max_attempts` times.
+    ///
+    ///`max_attempts` of `0` is treated as `1`.
+    pub const fn new(inner: G, predicate: P, max_attempts: usize) -> Self {
+        Self {
+            inner,
+            predicate,
+            max_attempts,
+        }
+    }
+
+    fn retry<O>(&self, gen: impl Fn() -> O) -> O where P: Fn(&O) -> bool {
+        let mut id = gen();
+        for _ in 1..self.max_attempts {
+            if (self.predicate)(&id) {
+                break;
+            }
+            id = gen();
+        }
+        id
+    }
+}
+
+impl<G: fmt::Debug, P> fmt::Debug for RetryGen<G, P> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RetryGen").field("inner", &self.inner).field("max_attempts", &self.max_attempts).finish()
+    }
+}
+
+impl<G: IdGen<O>, O, P: Fn(&O) -> bool> IdGen<O> for RetryGen<G, P> {
+    #[inline]
+    fn gen(&self) -> O {
+        self.retry(|| self.inner.gen())
+    }
+
+    #[inline]
+    fn gen_for(&self, req: RequestRef<'_>) -> O {
+        self.retry(|| self.inner.gen_for(req))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.inner.len_hint()
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &O) -> Option<http::HeaderValue> {
+        self.inner.header_value(id)
+    }
+
+    #[inline(always)]
+    fn header_value_fn(&self) -> Option<fn(&O) -> Option<http::HeaderValue>> {
+        self.inner.header_value_fn()
+    }
+
+    #[inline(always)]
+    fn write_id(&self) -> Option<fn(&O, &mut dyn fmt::Write) -> fmt::Result> {
+        self.inner.write_id()
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        self.inner.poll_ready(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    #[derive(Default)]
+    struct CounterGenerator(AtomicU64);
+
+    impl IdGen<u64> for CounterGenerator {
+        fn gen(&self) -> u64 {
+            self.0.fetch_add(1, Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn should_retry_until_predicate_holds() {
+        let gen = RetryGen::new(CounterGenerator::default(), |id: &u64| id % 3 == 2, 5);
+        assert_eq!(gen.gen(), 2);
+        assert_eq!(gen.inner.0.load(Ordering::Relaxed), 3);
+        assert_eq!(gen.gen(), 5);
+        assert_eq!(gen.inner.0.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    fn should_respect_max_attempts() {
+        let gen = RetryGen::new(CounterGenerator::default(), |_: &u64| false, 4);
+        assert_eq!(gen.gen(), 3);
+        assert_eq!(gen.inner.0.load(Ordering::Relaxed), 4);
+
+        let gen = RetryGen::new(CounterGenerator::default(), |_: &u64| false, 0);
+        assert_eq!(gen.gen(), 0);
+        assert_eq!(gen.inner.0.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,75 @@
+use core::fmt;
+use alloc::string::String;
+use alloc::sync::Arc;
+use std::sync::Mutex;
+
+use rand::RngCore;
+
+use crate::{utils, IdGen};
+
+///Generator of random tokens, drawing bytes from user provided RNG.
+///
+///Token is lower case hex string of specified number of random bytes, hence its length is always the same.
+///RNG is shared between clones of generator, which allows whole application to use single source of randomness (e.g. seeded RNG for reproducible simulations).
+///Note that order of generated tokens depends on order in which requests are processed.
+pub struct RngGenerator<R> {
+    rng: Arc<Mutex<R>>,
+    len: usize,
+}
+
+impl<R: RngCore> RngGenerator<R> {
+    #[inline]
+    ///Creates new instance, generating `len` random bytes per token out of `rng`.
+    pub fn new(rng: R, len: usize) -> Self {
+        Self::from_shared(Arc::new(Mutex::new(rng)), len)
+    }
+
+    #[inline(always)]
+    ///Creates new instance, using `rng`, which is shared with other users.
+    pub const fn from_shared(rng: Arc<Mutex<R>>, len: usize) -> Self {
+        Self {
+            rng,
+            len,
+        }
+    }
+
+    #[inline(always)]
+    ///Returns RNG, shared with generator.
+    pub fn rng(&self) -> &Arc<Mutex<R>> {
+        &self.rng
+    }
+}
+
+impl<R> Clone for RngGenerator<R> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            rng: self.rng.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<R> fmt::Debug for RngGenerator<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RngGenerator").field("len", &self.len).finish()
+    }
+}
+
+impl<R: RngCore> IdGen<String> for RngGenerator<R> {
+    fn gen(&self) -> String {
+        let mut random = alloc::vec![0u8; self.len];
+        {
+            //RNG state is valid regardless of panic, hence poisoning can be ignored.
+            let mut rng = self.rng.lock().unwrap_or_else(|error| error.into_inner());
+            rng.fill_bytes(&mut random);
+        }
+
+        utils::hex_string(&random)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.len * 2
+    }
+}
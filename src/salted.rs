@@ -0,0 +1,137 @@
+use core::fmt;
+use core::sync::atomic::{AtomicU64, Ordering};
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use crate::base62::{encode, encoded_len, Base62};
+
+//Number of bytes from mixed counter to encode.
+const CODE_BYTES: usize = 6;
+const MASK: u64 = (1 << (CODE_BYTES * 8)) - 1;
+
+//Bijective mixing of 48bit integer, so that distinct counters always produce distinct codes.
+const fn mix(mut num: u64) -> u64 {
+    num &= MASK;
+    num ^= num >> 24;
+    num = num.wrapping_mul(0x9e37_79b9_7f4b) & MASK;
+    num ^= num >> 23;
+    num = num.wrapping_mul(0xbf58_476d_1ce5) & MASK;
+    num ^= num >> 24;
+    num
+}
+
+#[derive(Clone)]
+///Generator of short base62 codes, combining random per-instance salt and monotonic counter.
+///
+///Within single generator (and its clones) codes are unique until counter wraps around after `2^48` IDs.
+///Salt makes sequences of generators in different processes or replicas distinct.
+pub struct SaltedCounterGenerator {
+    salt: u64,
+    counter: Arc<AtomicU64>,
+}
+
+impl SaltedCounterGenerator {
+    ///Creates new instance with random salt.
+    pub fn new() -> Self {
+        let mut salt = [0u8; 8];
+        crate::utils::fill_random(&mut salt);
+        Self::with_salt(u64::from_ne_bytes(salt))
+    }
+
+    #[inline]
+    ///Creates new instance with specified salt.
+    pub fn with_salt(salt: u64) -> Self {
+        Self {
+            salt,
+            counter: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    #[inline(always)]
+    ///Returns length of generated code.
+    pub const fn code_len(&self) -> usize {
+        encoded_len(CODE_BYTES)
+    }
+
+    ///Generates code.
+    pub fn gen(&self) -> String {
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        let code = mix(mix(counter) ^ self.salt);
+
+        let mut result = String::with_capacity(self.code_len());
+        encode(code.to_be_bytes()[8 - CODE_BYTES..].to_vec(), &mut result);
+        result
+    }
+}
+
+impl Default for SaltedCounterGenerator {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for SaltedCounterGenerator {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SaltedCounterGenerator").field("counter", &self.counter.load(Ordering::Relaxed)).finish()
+    }
+}
+
+impl super::IdGen<String> for SaltedCounterGenerator {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        Self::gen(self)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.code_len()
+    }
+}
+
+impl super::IdGen<Base62> for SaltedCounterGenerator {
+    #[inline(always)]
+    fn gen(&self) -> Base62 {
+        Base62(Self::gen(self))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.code_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::collections::HashSet;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn should_generate_short_unique_codes() {
+        let gen = SaltedCounterGenerator::with_salt(1);
+        let codes = (0..1000).map(|_| gen.gen()).collect::<Vec<_>>();
+        assert!(codes.iter().all(|code| code.len() == gen.code_len()));
+        assert_eq!(gen.code_len(), 9);
+        assert_eq!(codes.iter().collect::<HashSet<_>>().len(), codes.len());
+
+        //Clone continues the same sequence
+        let same = SaltedCounterGenerator::with_salt(1);
+        let clone = same.clone();
+        assert_eq!(same.gen(), codes[0]);
+        assert_eq!(clone.gen(), codes[1]);
+        assert_eq!(same.gen(), codes[2]);
+    }
+
+    #[test]
+    fn should_generate_distinct_sequences_for_distinct_salts() {
+        let first = SaltedCounterGenerator::with_salt(1);
+        let second = SaltedCounterGenerator::with_salt(2);
+
+        let first = (0..1000).map(|_| first.gen()).collect::<HashSet<_>>();
+        let second = (0..1000).map(|_| second.gen()).collect::<HashSet<_>>();
+        assert!(first.is_disjoint(&second));
+    }
+}
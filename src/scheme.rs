@@ -0,0 +1,174 @@
+use core::{fmt, task};
+use core::str::FromStr;
+use core::sync::atomic::{AtomicU64, Ordering};
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use crate::{utils, IdGen, IdType, RequestRef, GenerateRequestIdLayer, AlwaysValidHeader};
+
+///Scheme of ID, bundling its generation, parsing, validation and encoding.
+///
+///- Generation and encoding into header value are provided by `IdGen` implementation.
+///- Parsing and validation of ID from client are provided by this trait.
+///
+///Use `GenerateRequestIdLayer::with_scheme` to create layer out of scheme.
+pub trait IdScheme: IdGen<Self::Id> + Sized {
+    ///ID's type
+    type Id: IdType<Self>;
+
+    #[inline(always)]
+    ///Parses ID from client.
+    ///
+    ///Defaults to `FromStr` implementation.
+    fn parse(text: &str) -> Option<Self::Id> {
+        Self::Id::from_str(text).ok()
+    }
+
+    #[inline(always)]
+    ///Validates ID from client, after it is parsed.
+    ///
+    ///Invalid ID is replaced with generated one.
+    ///Defaults to accepting any ID.
+    fn validate(_id: &Self::Id) -> bool {
+        true
+    }
+}
+
+fn parse_scheme<S: IdScheme>(text: &str) -> Option<S::Id> {
+    match S::parse(text) {
+        Some(id) if S::validate(&id) => Some(id),
+        _ => None,
+    }
+}
+
+impl<S: IdScheme> GenerateRequestIdLayer<S, S::Id> {
+    #[inline(always)]
+    ///Creates new instance, which uses `scheme` to generate, parse, validate and encode ID.
+    ///
+    ///Scheme is plugged in via `parse_with`, hence specifying it afterwards overrides scheme's parsing.
+    pub const fn with_scheme(scheme: S) -> Self {
+        Self::new(scheme).parse_with(parse_scheme::<S>)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Scheme of `String` ID, produced by generator `G`.
+///
+///ID from client is accepted only if it is valid header value, so it is always echoed in response.
+pub struct StringScheme<G> {
+    inner: G,
+}
+
+impl<G> StringScheme<G> {
+    #[inline(always)]
+    ///Creates new instance.
+    pub const fn new(inner: G) -> Self {
+        Self {
+            inner,
+        }
+    }
+}
+
+impl<G: IdGen<String>> IdGen<String> for StringScheme<G> {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        self.inner.gen()
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> String {
+        self.inner.gen_for(req)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.inner.len_hint()
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &String) -> Option<http::HeaderValue> {
+        self.inner.header_value(id)
+    }
+
+    #[inline(always)]
+    fn write_id(&self) -> Option<fn(&String, &mut dyn fmt::Write) -> fmt::Result> {
+        self.inner.write_id()
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        self.inner.poll_ready(ctx)
+    }
+}
+
+impl<G: IdGen<String>> IdScheme for StringScheme<G> {
+    type Id = String;
+
+    #[inline(always)]
+    fn validate(id: &String) -> bool {
+        utils::is_safe_value(id.as_bytes())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+///Scheme of numeric ID, generated by incrementing counter starting from `1`.
+///
+///Counter is shared between clones of the scheme.
+///ID from client must be positive decimal number.
+pub struct CounterScheme {
+    counter: Arc<AtomicU64>,
+}
+
+impl CounterScheme {
+    #[inline]
+    ///Creates new instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdGen<u64> for CounterScheme {
+    #[inline(always)]
+    fn gen(&self) -> u64 {
+        self.counter.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        //u64::MAX is 20 digits
+        20
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &u64) -> Option<http::HeaderValue> {
+        Some(id.to_header_value(0, 0))
+    }
+}
+
+impl IdScheme for CounterScheme {
+    type Id = u64;
+
+    #[inline(always)]
+    fn parse(text: &str) -> Option<u64> {
+        //Unlike `FromStr`, reject leading `+`
+        match text.bytes().all(|byte| byte.is_ascii_digit()) {
+            true => text.parse().ok(),
+            false => None,
+        }
+    }
+
+    #[inline(always)]
+    fn validate(id: &u64) -> bool {
+        *id > 0
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl IdScheme for crate::UuidGenerator {
+    type Id = crate::Uuid;
+
+    #[inline(always)]
+    fn parse(text: &str) -> Option<crate::Uuid> {
+        crate::Uuid::parse_str(text).ok()
+    }
+}
@@ -0,0 +1,49 @@
+use core::any::Any;
+use core::cell::RefCell;
+use alloc::boxed::Box;
+
+pub(crate) type ScopedId = Option<Box<dyn Any + Send + Sync>>;
+
+std::thread_local! {
+    static CURRENT: RefCell<ScopedId> = const { RefCell::new(None) };
+}
+
+#[inline]
+fn swap(id: &mut ScopedId) {
+    CURRENT.with(|current| core::mem::swap(&mut *current.borrow_mut(), id));
+}
+
+//Makes ID current while alive, restoring previous one on drop.
+pub(crate) struct ScopeGuard<'a> {
+    id: &'a mut ScopedId,
+}
+
+impl<'a> ScopeGuard<'a> {
+    #[inline]
+    pub(crate) fn enter(id: &'a mut ScopedId) -> Self {
+        swap(id);
+        Self {
+            id,
+        }
+    }
+}
+
+impl Drop for ScopeGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        swap(self.id);
+    }
+}
+
+///Returns ID of the request, which is currently being processed.
+///
+///ID is available only while inner service's future is being polled by middleware configured with `scope_id`.
+///Tasks spawned from the request's handler do not inherit it.
+///
+///Returns `None` if there is no ID in scope or it is not of type `O`.
+pub fn current_request_id<O: Clone + 'static>() -> Option<O> {
+    CURRENT.try_with(|current| match current.borrow().as_ref() {
+        Some(id) => id.downcast_ref::<O>().cloned(),
+        None => None,
+    }).ok().flatten()
+}
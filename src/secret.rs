@@ -0,0 +1,72 @@
+use core::{convert, fmt, ops, str};
+use alloc::string::String;
+
+use zeroize::Zeroize;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+///ID containing sensitive data, which is wiped from memory on drop.
+///
+///Only memory owned by this type is zeroized.
+///Response header, as well as request header from which ID is parsed, contain their own copies, which are not zeroized.
+///`Debug` implementation doesn't reveal ID.
+pub struct SecretId(String);
+
+impl SecretId {
+    #[inline(always)]
+    ///Creates new instance.
+    pub fn new(id: String) -> Self {
+        Self(id)
+    }
+
+    #[inline(always)]
+    ///Returns underlying string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for SecretId {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ops::Deref for SecretId {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretId {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("SecretId(..)")
+    }
+}
+
+impl fmt::Display for SecretId {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl crate::FromHeaderStr for SecretId {
+    #[inline(always)]
+    fn from_header_str(text: &str) -> Self {
+        Self(text.into())
+    }
+}
+
+impl str::FromStr for SecretId {
+    type Err = convert::Infallible;
+
+    #[inline(always)]
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(Self(text.into()))
+    }
+}
@@ -0,0 +1,55 @@
+use core::fmt;
+
+use crate::{IdGen, RequestRef};
+
+///Generator deriving ID from seed `T`, stored within request's extensions by preceding layers.
+///
+///If request has no seed, then it fallbacks to generator `G`.
+pub struct SeededGenerator<T, G, O> {
+    fallback: G,
+    derive: fn(&T) -> O,
+}
+
+impl<T, G, O> SeededGenerator<T, G, O> {
+    #[inline(always)]
+    ///Creates new instance, which derives ID from seed using `derive` function.
+    pub const fn new(fallback: G, derive: fn(&T) -> O) -> Self {
+        Self {
+            fallback,
+            derive,
+        }
+    }
+}
+
+impl<T, G: Clone, O> Clone for SeededGenerator<T, G, O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self::new(self.fallback.clone(), self.derive)
+    }
+}
+
+impl<T, G: fmt::Debug, O> fmt::Debug for SeededGenerator<T, G, O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SeededGenerator").field("fallback", &self.fallback).finish()
+    }
+}
+
+impl<T: Send + Sync + 'static, G: IdGen<O>, O> IdGen<O> for SeededGenerator<T, G, O> {
+    #[inline(always)]
+    fn gen(&self) -> O {
+        self.fallback.gen()
+    }
+
+    #[inline]
+    fn gen_for(&self, req: RequestRef<'_>) -> O {
+        match req.extensions().get::<T>() {
+            Some(seed) => (self.derive)(seed),
+            None => self.fallback.gen_for(req),
+        }
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.fallback.len_hint()
+    }
+}
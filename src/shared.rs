@@ -0,0 +1,106 @@
+use core::{convert, fmt, ops, str};
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use crate::{IdGen, RequestRef};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+///ID backed by `Arc<str>`, which makes cloning it cheap.
+///
+///This is useful when ID is accessed by many tasks during request's processing.
+///Like `String`, it accepts any ID from client.
+pub struct SharedId(pub Arc<str>);
+
+impl SharedId {
+    #[inline(always)]
+    ///Returns underlying string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ops::Deref for SharedId {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<String> for SharedId {
+    #[inline(always)]
+    fn from(id: String) -> Self {
+        Self(id.into())
+    }
+}
+
+impl From<&str> for SharedId {
+    #[inline(always)]
+    fn from(id: &str) -> Self {
+        Self(id.into())
+    }
+}
+
+impl From<SharedId> for Arc<str> {
+    #[inline(always)]
+    fn from(id: SharedId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for SharedId {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl crate::FromHeaderStr for SharedId {
+    #[inline(always)]
+    fn from_header_str(text: &str) -> Self {
+        text.into()
+    }
+}
+
+impl str::FromStr for SharedId {
+    type Err = convert::Infallible;
+
+    #[inline(always)]
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(text.into())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Adapter of `String` generator, producing `SharedId`.
+pub struct SharedGenerator<G> {
+    inner: G,
+}
+
+impl<G> SharedGenerator<G> {
+    #[inline(always)]
+    ///Creates new instance.
+    pub const fn new(inner: G) -> Self {
+        Self {
+            inner,
+        }
+    }
+}
+
+impl<G: IdGen<String>> IdGen<SharedId> for SharedGenerator<G> {
+    #[inline(always)]
+    fn gen(&self) -> SharedId {
+        self.inner.gen().into()
+    }
+
+    #[inline(always)]
+    fn gen_for(&self, req: RequestRef<'_>) -> SharedId {
+        self.inner.gen_for(req).into()
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.inner.len_hint()
+    }
+}
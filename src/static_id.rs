@@ -0,0 +1,160 @@
+use core::{fmt, ops, str};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+///Static ID, which is never accepted from client.
+///
+///Its `FromStr` implementation always fails, hence ID is always generated.
+pub struct StaticId(pub &'static str);
+
+impl StaticId {
+    #[inline(always)]
+    ///Returns underlying string.
+    pub const fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl ops::Deref for StaticId {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl fmt::Display for StaticId {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.0)
+    }
+}
+
+impl str::FromStr for StaticId {
+    type Err = ();
+
+    #[inline(always)]
+    fn from_str(_: &str) -> Result<Self, Self::Err> {
+        Err(())
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+///Generator of compile-time static ID.
+///
+///If ID is valid header value, then it is written into response via `HeaderValue::from_static`, avoiding any allocation and formatting.
+pub struct StaticGenerator {
+    id: &'static str,
+    is_valid: bool,
+}
+
+impl StaticGenerator {
+    ///Creates new instance.
+    pub const fn new(id: &'static str) -> Self {
+        Self {
+            id,
+            is_valid: is_static_value(id.as_bytes()),
+        }
+    }
+
+    ///Creates new instance, failing if ID is not valid header value.
+    ///
+    ///This allows to detect invalid ID on construction, instead of silently omitting response header.
+    pub const fn try_new(id: &'static str) -> Result<Self, crate::RequestIdError> {
+        let result = Self::new(id);
+        match result.is_valid {
+            true => Ok(result),
+            false => Err(crate::RequestIdError::InvalidHeaderValue),
+        }
+    }
+}
+
+//Mirrors `HeaderValue::from_static` requirements, additionally rejecting values that would be handled by `InvalidValue`
+const fn is_static_value(value: &[u8]) -> bool {
+    if let [b' ', ..] | [.., b' '] = value {
+        return false;
+    }
+
+    let mut idx = 0;
+    while idx < value.len() {
+        if value[idx] < 0x20 || value[idx] > 0x7e {
+            return false;
+        }
+        idx += 1;
+    }
+
+    true
+}
+
+impl super::IdGen<StaticId> for StaticGenerator {
+    #[inline(always)]
+    fn gen(&self) -> StaticId {
+        StaticId(self.id)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.id.len()
+    }
+
+    #[inline(always)]
+    fn header_value(&self, id: &StaticId) -> Option<http::HeaderValue> {
+        match self.is_valid && id.0 == self.id {
+            true => Some(http::HeaderValue::from_static(self.id)),
+            false => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+///Generator of constant ID.
+///
+///ID's header value is formatted once on creation, and then re-used for every response with the same ID.
+pub struct ConstGenerator<O> {
+    id: O,
+    header_value: Option<http::HeaderValue>,
+}
+
+impl<O: fmt::Display> ConstGenerator<O> {
+    #[inline]
+    ///Creates new instance.
+    pub fn new(id: O) -> Self {
+        Self {
+            header_value: crate::to_header_value(&id).ok(),
+            id,
+        }
+    }
+
+    #[inline]
+    ///Creates new instance, failing if ID's textual representation is not valid header value.
+    pub fn try_new(id: O) -> Result<Self, crate::RequestIdError> {
+        let header_value = crate::to_header_value(&id)?;
+        Ok(Self {
+            header_value: Some(header_value),
+            id,
+        })
+    }
+}
+
+impl<O: Clone + PartialEq> super::IdGen<O> for ConstGenerator<O> {
+    #[inline(always)]
+    fn gen(&self) -> O {
+        self.id.clone()
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        match &self.header_value {
+            Some(header_value) => header_value.len(),
+            None => super::DEFAULT_LEN_HINT,
+        }
+    }
+
+    #[inline]
+    fn header_value(&self, id: &O) -> Option<http::HeaderValue> {
+        match *id == self.id {
+            true => self.header_value.clone(),
+            false => None,
+        }
+    }
+}
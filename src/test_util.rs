@@ -0,0 +1,194 @@
+//!Utilities to test ID generators.
+
+use core::hash::Hash;
+use core::task;
+use std::collections::HashSet;
+use std::string::String;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::format;
+
+use crate::IdGen;
+
+///Generates `n` IDs using `gen`, checking that all of them are distinct.
+///
+///Returns `Err` with first duplicate ID, if any.
+pub fn assert_unique<G: IdGen<O>, O: Eq + Hash>(gen: &G, n: usize) -> Result<(), O> {
+    let mut unique = HashSet::with_capacity(n);
+    for _ in 0..n {
+        let id = gen.gen();
+        if unique.contains(&id) {
+            return Err(id);
+        }
+        unique.insert(id);
+    }
+
+    Ok(())
+}
+
+///Deterministic generator of IDs in form of `<prefix>-<seq>`, e.g. `test-001`, `test-002`.
+///
+///Sequence starts from `1`, is zero padded to 3 digits and is shared between clones of generator.
+///Use `reset` to start sequence over between test scenarios.
+///
+///```rust
+///use tower_http_req_id::IdGen;
+///use tower_http_req_id::test_util::SeqTestGenerator;
+///
+///let gen = SeqTestGenerator::new("test");
+///assert_eq!(IdGen::<String>::gen(&gen), "test-001");
+///assert_eq!(IdGen::<String>::gen(&gen.clone()), "test-002");
+///
+///gen.reset();
+///assert_eq!(IdGen::<String>::gen(&gen), "test-001");
+///```
+#[derive(Clone, Debug)]
+pub struct SeqTestGenerator {
+    prefix: &'static str,
+    seq: Arc<AtomicUsize>,
+}
+
+impl SeqTestGenerator {
+    #[inline]
+    ///Creates new generator with specified `prefix`.
+    pub fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix,
+            seq: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    #[inline]
+    ///Restarts sequence, so that next ID is `<prefix>-001`.
+    pub fn reset(&self) {
+        self.seq.store(0, Ordering::Relaxed);
+    }
+}
+
+impl IdGen<String> for SeqTestGenerator {
+    #[inline]
+    fn gen(&self) -> String {
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed) + 1;
+        format!("{}-{:03}", self.prefix, seq)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.prefix.len() + 4
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Fault, injected by `FaultyGenerator`.
+pub enum Fault {
+    ///ID contains bytes, which are not allowed in header value (`\r\n`).
+    InvalidHeaderBytes,
+    ///ID is empty string.
+    Empty,
+    ///ID is padded with `x` to specified length in bytes.
+    TooLong(usize),
+    ///Generator never becomes ready, which is the only way `IdGen` can fail.
+    ///
+    ///IDs generated regardless are valid.
+    Unavailable,
+}
+
+///Generator, which injects specified fault, allowing to test fallback paths of ID handling.
+///
+///Unless fault is specified, it generates `id` as it is.
+///Fault can be limited to first few generations via `times`, after which valid `id` is generated.
+///
+///```rust
+///use tower_http_req_id::IdGen;
+///use tower_http_req_id::test_util::{Fault, FaultyGenerator};
+///
+///let gen = FaultyGenerator::new("id").fault(Fault::Empty).times(1);
+///assert_eq!(IdGen::<String>::gen(&gen), "");
+///assert_eq!(IdGen::<String>::gen(&gen.clone()), "id");
+///```
+#[derive(Clone, Debug)]
+pub struct FaultyGenerator {
+    id: &'static str,
+    fault: Option<Fault>,
+    //Remaining number of faults, shared between clones
+    remaining: Option<Arc<AtomicUsize>>,
+}
+
+impl FaultyGenerator {
+    #[inline(always)]
+    ///Creates new generator of valid `id`.
+    pub const fn new(id: &'static str) -> Self {
+        Self {
+            id,
+            fault: None,
+            remaining: None,
+        }
+    }
+
+    #[inline(always)]
+    ///Specifies fault to inject.
+    pub const fn fault(mut self, fault: Fault) -> Self {
+        self.fault = Some(fault);
+        self
+    }
+
+    #[inline]
+    ///Limits fault to first `times` generations (or readiness checks in case of `Fault::Unavailable`).
+    ///
+    ///By default fault is injected always.
+    pub fn times(mut self, times: usize) -> Self {
+        self.remaining = Some(Arc::new(AtomicUsize::new(times)));
+        self
+    }
+
+    //Consumes one fault, returning it if it is to be injected.
+    fn next_fault(&self) -> Option<Fault> {
+        let fault = self.fault?;
+        match &self.remaining {
+            Some(remaining) => match remaining.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| remaining.checked_sub(1)) {
+                Ok(_) => Some(fault),
+                Err(_) => None,
+            },
+            None => Some(fault),
+        }
+    }
+}
+
+impl IdGen<String> for FaultyGenerator {
+    fn gen(&self) -> String {
+        match self.fault {
+            Some(Fault::Unavailable) | None => return self.id.into(),
+            Some(_) => (),
+        }
+
+        match self.next_fault() {
+            Some(Fault::InvalidHeaderBytes) => format!("{}\r\n", self.id),
+            Some(Fault::Empty) => String::new(),
+            Some(Fault::TooLong(len)) => format!("{:x<len$}", self.id, len = len),
+            Some(Fault::Unavailable) | None => self.id.into(),
+        }
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.id.len()
+    }
+
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        match self.fault {
+            Some(Fault::Unavailable) => (),
+            _ => return task::Poll::Ready(()),
+        }
+
+        match self.next_fault() {
+            Some(_) => {
+                //Unlimited fault never becomes ready, hence there is no point to poll it again.
+                if self.remaining.is_some() {
+                    ctx.waker().wake_by_ref();
+                }
+                task::Poll::Pending
+            },
+            None => task::Poll::Ready(()),
+        }
+    }
+}
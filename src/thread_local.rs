@@ -0,0 +1,146 @@
+use core::{fmt, task};
+use core::any::Any;
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+
+use crate::{IdGen, RequestRef};
+
+static NEXT_KEY: AtomicUsize = AtomicUsize::new(0);
+
+std::thread_local! {
+    //Per-thread state of every `ThreadLocalGenerator`, keyed by generator's key.
+    static STATES: RefCell<BTreeMap<usize, Rc<dyn Any>>> = RefCell::new(BTreeMap::new());
+}
+
+///Generator, which keeps state of inner generator in thread local storage.
+///
+///Inner generator is created by calling `init` lazily, once per thread on which ID is generated.
+///Clones share the same per-thread state and are as cheap as cloning `init`, which is useful for generators with expensive to create or clone state.
+///
+///Inner generator does not need to be `Send` or `Sync`, as it never leaves its thread.
+///But as consequence, IDs generated on different threads come from independent states (e.g. counters are not shared).
+///
+///Note that state is released only when its thread exits, even if all clones of generator are dropped.
+///Hence it is intended for generators, which live as long as application itself.
+pub struct ThreadLocalGenerator<F> {
+    init: F,
+    key: usize,
+}
+
+impl<F> ThreadLocalGenerator<F> {
+    #[inline]
+    ///Creates new instance, using `init` to create inner generator on each thread.
+    pub fn new(init: F) -> Self {
+        Self {
+            init,
+            key: NEXT_KEY.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    fn with_state<G: 'static, R>(&self, cb: impl FnOnce(&G) -> R) -> R where F: Fn() -> G {
+        //Borrow is not held while calling inner generator, in case it uses thread local generator too.
+        let state = STATES.with(|states| {
+            if let Some(state) = states.borrow().get(&self.key) {
+                return state.clone();
+            }
+
+            let state: Rc<dyn Any> = Rc::new((self.init)());
+            states.borrow_mut().insert(self.key, state.clone());
+            state
+        });
+
+        match state.downcast_ref::<G>() {
+            Some(state) => cb(state),
+            //Key is unique to generator and its clones, all of which share the same `F`
+            None => unreachable!(),
+        }
+    }
+}
+
+impl<F: Clone> Clone for ThreadLocalGenerator<F> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            init: self.init.clone(),
+            key: self.key,
+        }
+    }
+}
+
+impl<F> fmt::Debug for ThreadLocalGenerator<F> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ThreadLocalGenerator").field("key", &self.key).finish()
+    }
+}
+
+impl<F: Fn() -> G, G: IdGen<O> + 'static, O> IdGen<O> for ThreadLocalGenerator<F> {
+    #[inline]
+    fn gen(&self) -> O {
+        self.with_state(|state: &G| state.gen())
+    }
+
+    #[inline]
+    fn gen_for(&self, req: RequestRef<'_>) -> O {
+        self.with_state(|state: &G| state.gen_for(req))
+    }
+
+    #[inline]
+    fn len_hint(&self) -> usize {
+        self.with_state(|state: &G| state.len_hint())
+    }
+
+    #[inline]
+    fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+        self.with_state(|state: &G| state.poll_ready(ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    //Counter is deliberately neither `Send` nor `Sync`
+    struct CounterGenerator(Rc<Cell<u64>>);
+
+    impl IdGen<u64> for CounterGenerator {
+        fn gen(&self) -> u64 {
+            self.0.set(self.0.get() + 1);
+            self.0.get()
+        }
+    }
+
+    fn new_counter() -> CounterGenerator {
+        CounterGenerator(Rc::new(Cell::new(0)))
+    }
+
+    #[test]
+    fn verify_state_is_shared_within_thread() {
+        let gen = ThreadLocalGenerator::new(new_counter as fn() -> CounterGenerator);
+        let clone = gen.clone();
+
+        assert_eq!(gen.gen(), 1);
+        assert_eq!(clone.gen(), 2);
+        assert_eq!(gen.gen(), 3);
+
+        //Separate generator has own state
+        let other = ThreadLocalGenerator::new(new_counter as fn() -> CounterGenerator);
+        assert_eq!(other.gen(), 1);
+        assert_eq!(gen.gen(), 4);
+    }
+
+    #[test]
+    fn verify_state_is_independent_between_threads() {
+        let gen = ThreadLocalGenerator::new(new_counter as fn() -> CounterGenerator);
+        assert_eq!(gen.gen(), 1);
+        assert_eq!(gen.gen(), 2);
+
+        let clone = gen.clone();
+        let other_thread = std::thread::spawn(move || (clone.gen(), clone.gen())).join().expect("thread to succeed");
+        assert_eq!(other_thread, (1, 2));
+
+        assert_eq!(gen.gen(), 3);
+    }
+}
@@ -0,0 +1,58 @@
+use alloc::string::String;
+
+use crate::{IdGen, RequestRef};
+
+std::thread_local! {
+    //Numeric part of current thread's id, formatted once per thread.
+    static THREAD_TAG: String = {
+        let id = alloc::format!("{:?}", std::thread::current().id());
+        id.chars().filter(char::is_ascii_digit).collect()
+    };
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Generator, which prefixes `String` ID of inner generator with current thread's ID: `{thread_id}-{id}`.
+///
+///This is useful to find out which worker handled the request.
+///Thread ID is unique only within single process.
+pub struct ThreadTaggedGenerator<G> {
+    inner: G,
+}
+
+impl<G> ThreadTaggedGenerator<G> {
+    #[inline(always)]
+    ///Creates new instance.
+    pub const fn new(inner: G) -> Self {
+        Self {
+            inner,
+        }
+    }
+}
+
+fn tag(id: &str) -> String {
+    THREAD_TAG.with(|tag| {
+        let mut result = String::with_capacity(tag.len() + 1 + id.len());
+        result.push_str(tag);
+        result.push('-');
+        result.push_str(id);
+        result
+    })
+}
+
+impl<G: IdGen<String>> IdGen<String> for ThreadTaggedGenerator<G> {
+    #[inline]
+    fn gen(&self) -> String {
+        tag(&self.inner.gen())
+    }
+
+    #[inline]
+    fn gen_for(&self, req: RequestRef<'_>) -> String {
+        tag(&self.inner.gen_for(req))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        //Thread ids rarely exceed few digits
+        4 + self.inner.len_hint()
+    }
+}
@@ -0,0 +1,132 @@
+use core::fmt::Write;
+use alloc::string::String;
+
+use crate::base62::{encode, encoded_len};
+use crate::clock::Clock;
+
+//Number of base36 digits for time, enough for milliseconds until year 5138.
+const TIME_LEN: usize = 9;
+//Number of random bytes.
+const RANDOM_BYTES: usize = 8;
+
+//Writes `num` as lower case base36 with at least `width` digits.
+fn write_base36(out: &mut String, mut num: u64, width: usize) {
+    const ALPHABET: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    let mut digits = [b'0'; 13];
+    let mut len = 0;
+    while num > 0 {
+        digits[len] = ALPHABET[(num % 36) as usize];
+        num /= 36;
+        len += 1;
+    }
+
+    for _ in len..width {
+        out.push('0');
+    }
+    out.extend(digits[..len].iter().rev().map(|digit| *digit as char));
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+///Generator of roughly sortable IDs in format `{unix_ms_base36}-{random_base62}`.
+///
+///Time is zero padded, hence lexical order of IDs follows their creation time with millisecond precision.
+///IDs created within the same millisecond are ordered randomly.
+pub struct TimeSortableGenerator<C> {
+    clock: C,
+}
+
+#[cfg(feature = "std")]
+impl TimeSortableGenerator<crate::clock::SystemClock> {
+    #[inline(always)]
+    ///Creates new instance, using system clock.
+    pub const fn new() -> Self {
+        Self::with_clock(crate::clock::SystemClock)
+    }
+}
+
+impl<C: Clock> TimeSortableGenerator<C> {
+    #[inline(always)]
+    ///Creates new instance, using specified clock.
+    pub const fn with_clock(clock: C) -> Self {
+        Self {
+            clock,
+        }
+    }
+
+    #[inline(always)]
+    ///Returns expected length of generated ID.
+    pub const fn id_len(&self) -> usize {
+        TIME_LEN + 1 + encoded_len(RANDOM_BYTES)
+    }
+
+    ///Generates ID.
+    pub fn gen(&self) -> String {
+        let mut random = alloc::vec![0u8; RANDOM_BYTES];
+        crate::utils::fill_random(&mut random);
+
+        let mut result = String::with_capacity(self.id_len());
+        write_base36(&mut result, self.clock.unix_time().as_millis() as u64, TIME_LEN);
+        let _ = result.write_char('-');
+        encode(random, &mut result);
+        result
+    }
+}
+
+impl<C: Clock> super::IdGen<String> for TimeSortableGenerator<C> {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        Self::gen(self)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.id_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::time::Duration;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    struct TestClock(AtomicU64);
+
+    impl Clock for TestClock {
+        fn unix_time(&self) -> Duration {
+            Duration::from_millis(self.0.fetch_add(1, Ordering::Relaxed))
+        }
+    }
+
+    #[test]
+    fn should_write_zero_padded_base36() {
+        let mut out = String::new();
+        write_base36(&mut out, 0, TIME_LEN);
+        assert_eq!(out, "000000000");
+
+        out.clear();
+        write_base36(&mut out, 1_700_000_000_000, TIME_LEN);
+        assert_eq!(out, "0loyw3v28");
+
+        out.clear();
+        write_base36(&mut out, u64::MAX, TIME_LEN);
+        assert_eq!(out, "3w5e11264sgsf");
+    }
+
+    #[test]
+    fn should_generate_sortable_ids() {
+        let gen = TimeSortableGenerator::with_clock(TestClock(AtomicU64::new(35)));
+
+        let mut previous = gen.gen();
+        assert_eq!(previous.len(), gen.id_len());
+        assert!(previous.starts_with("00000000z-"));
+        for _ in 0..100 {
+            let id = gen.gen();
+            assert_eq!(id.len(), gen.id_len());
+            assert!(id > previous, "{} <= {}", id, previous);
+            previous = id;
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use core::{fmt, task};
+use core::pin::Pin;
+use core::future::Future;
+use core::marker::PhantomData;
+
+use http::{Request, Response};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{HEADER, DEFAULT_LEN_HINT, InvalidValue, format_header_value};
+
+///Layer for writing request id into response's trailers.
+///
+///It must be placed inside of `GenerateRequestIdLayer`, as ID of type `O` is taken from request's extensions.
+///Response body is wrapped into `TrailerBody`, which appends ID to body's trailers, if any.
+///
+///Note that trailers are only delivered when protocol supports them (e.g. HTTP/2, or chunked HTTP/1.1 response to client, accepting trailers).
+///To write ID only into trailers, disable response header using `GenerateRequestIdLayer::response_header_when`.
+pub struct RequestIdTrailerLayer<O> {
+    header_name: http::HeaderName,
+    _id: PhantomData<fn() -> O>,
+}
+
+impl<O> RequestIdTrailerLayer<O> {
+    #[inline(always)]
+    ///Creates new instance
+    pub const fn new() -> Self {
+        Self {
+            header_name: HEADER,
+            _id: PhantomData,
+        }
+    }
+
+    #[inline]
+    ///Specifies trailer name to write ID into.
+    ///
+    ///Defaults to `x-request-id`.
+    pub fn header_name(mut self, name: http::HeaderName) -> Self {
+        self.header_name = name;
+        self
+    }
+}
+
+impl<O> Default for RequestIdTrailerLayer<O> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O> Clone for RequestIdTrailerLayer<O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            header_name: self.header_name.clone(),
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<O> fmt::Debug for RequestIdTrailerLayer<O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RequestIdTrailerLayer").field("header_name", &self.header_name).finish()
+    }
+}
+
+impl<S, O> Layer<S> for RequestIdTrailerLayer<O> {
+    type Service = RequestIdTrailer<S, O>;
+
+    #[inline(always)]
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdTrailer {
+            inner,
+            header_name: self.header_name.clone(),
+            _id: PhantomData,
+        }
+    }
+}
+
+///Service for writing request id into response's trailers.
+///
+///See `RequestIdTrailerLayer` for details.
+pub struct RequestIdTrailer<S, O> {
+    inner: S,
+    header_name: http::HeaderName,
+    _id: PhantomData<fn() -> O>,
+}
+
+impl<S: Clone, O> Clone for RequestIdTrailer<S, O> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            header_name: self.header_name.clone(),
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, O> fmt::Debug for RequestIdTrailer<S, O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RequestIdTrailer").field("inner", &self.inner).field("header_name", &self.header_name).finish()
+    }
+}
+
+impl<ReqBody, ResBody, S: Service<Request<ReqBody>, Response = Response<ResBody>>, O: fmt::Display + Send + Sync + 'static> Service<Request<ReqBody>> for RequestIdTrailer<S, O> {
+    type Response = Response<TrailerBody<ResBody>>;
+    type Error = S::Error;
+    type Future = TrailerFut<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, ctx: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(ctx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let value = req.extensions().get::<O>().and_then(|id| format_header_value(id, DEFAULT_LEN_HINT, 0, InvalidValue::Skip));
+
+        TrailerFut {
+            inner: self.inner.call(req),
+            trailer: value.map(|value| (self.header_name.clone(), value)),
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    ///Future wrapping response's body into `TrailerBody`.
+    pub struct TrailerFut<F> {
+        #[pin]
+        inner: F,
+        trailer: Option<(http::HeaderName, http::HeaderValue)>,
+    }
+}
+
+impl<B, E, F: Future<Output = Result<Response<B>, E>>> Future for TrailerFut<F> {
+    type Output = Result<Response<TrailerBody<B>>, E>;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+        let this = self.project();
+
+        let resp = match Future::poll(this.inner, ctx) {
+            task::Poll::Ready(resp) => resp?,
+            task::Poll::Pending => return task::Poll::Pending,
+        };
+
+        let trailer = this.trailer.take();
+        task::Poll::Ready(Ok(resp.map(|inner| TrailerBody {
+            inner,
+            trailer,
+        })))
+    }
+}
+
+pin_project_lite::pin_project! {
+    ///Body, which appends request id to the trailers of inner body.
+    ///
+    ///If inner body has trailer with the same name, it is replaced.
+    pub struct TrailerBody<B> {
+        #[pin]
+        inner: B,
+        trailer: Option<(http::HeaderName, http::HeaderValue)>,
+    }
+}
+
+impl<B> TrailerBody<B> {
+    #[inline(always)]
+    ///Returns reference to inner body.
+    pub fn get_ref(&self) -> &B {
+        &self.inner
+    }
+
+    #[inline(always)]
+    ///Returns inner body, dropping trailer.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: Default> Default for TrailerBody<B> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            inner: B::default(),
+            trailer: None,
+        }
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for TrailerBody<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("TrailerBody").field("inner", &self.inner).field("trailer", &self.trailer).finish()
+    }
+}
+
+impl<B: http_body::Body> http_body::Body for TrailerBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    #[inline(always)]
+    fn poll_data(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Option<Result<Self::Data, Self::Error>>> {
+        self.project().inner.poll_data(ctx)
+    }
+
+    fn poll_trailers(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let this = self.project();
+
+        let trailers = match this.inner.poll_trailers(ctx) {
+            task::Poll::Ready(trailers) => trailers?,
+            task::Poll::Pending => return task::Poll::Pending,
+        };
+
+        match this.trailer.take() {
+            Some((name, value)) => {
+                let mut trailers = trailers.unwrap_or_default();
+                trailers.insert(name, value);
+                task::Poll::Ready(Ok(Some(trailers)))
+            },
+            None => task::Poll::Ready(Ok(trailers)),
+        }
+    }
+
+    #[inline(always)]
+    fn is_end_stream(&self) -> bool {
+        self.trailer.is_none() && self.inner.is_end_stream()
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -7,12 +7,18 @@ pub struct BytesWriter {
 
 impl BytesWriter {
     #[inline(always)]
-    pub fn new() -> Self {
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            buf: bytes::BytesMut::with_capacity(10)
+            buf: bytes::BytesMut::with_capacity(capacity)
         }
     }
 
+    #[inline(always)]
+    ///Left-pads written bytes with zeros up to `width`, refer to `zero_pad_mut`.
+    pub fn zero_pad(&mut self, width: usize) {
+        zero_pad_mut(&mut self.buf, width)
+    }
+
     #[inline(always)]
     ///Converts into `bytes::Bytes`
     pub fn freeze(&mut self) -> bytes::Bytes {
@@ -27,3 +33,405 @@ impl fmt::Write for BytesWriter {
         Ok(())
     }
 }
+
+#[inline(always)]
+fn is_ws(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t'
+}
+
+#[inline(always)]
+fn is_ctl(byte: u8) -> bool {
+    byte < 0x20 || byte == 0x7f
+}
+
+///Checks that header value has no control characters or surrounding whitespace.
+pub fn is_safe_value(value: &[u8]) -> bool {
+    match (value.first(), value.last()) {
+        (Some(first), Some(last)) if is_ws(*first) || is_ws(*last) => false,
+        _ => !value.iter().any(|byte| is_ctl(*byte)),
+    }
+}
+
+///Percent-encodes every byte outside of visible ASCII range as well as `%` itself.
+pub fn percent_encode(value: &[u8]) -> bytes::Bytes {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut result = bytes::BytesMut::with_capacity(value.len() * 3);
+    for byte in value.iter().copied() {
+        if byte > 0x20 && byte < 0x7f && byte != b'%' {
+            result.extend_from_slice(&[byte]);
+        } else {
+            result.extend_from_slice(&[b'%', HEX[(byte >> 4) as usize], HEX[(byte & 0xf) as usize]]);
+        }
+    }
+    result.freeze()
+}
+
+///Left-pads `value` with zeros up to `width` bytes in place.
+///
+///Leading minus sign is kept in front of zeros (e.g. `-0042`), so that negative integer remains valid.
+pub fn zero_pad_mut(value: &mut bytes::BytesMut, width: usize) {
+    let len = value.len();
+    if let Some(pad) = width.checked_sub(len).filter(|pad| *pad > 0) {
+        let sign = match value.first() {
+            Some(b'-') => 1,
+            _ => 0,
+        };
+        value.resize(width, b'0');
+        value.copy_within(sign..len, sign + pad);
+        value[sign..sign + pad].fill(b'0');
+    }
+}
+
+///Left-pads `value` with zeros up to `width` bytes, refer to `zero_pad_mut`.
+pub fn zero_pad(value: bytes::Bytes, width: usize) -> bytes::Bytes {
+    match value.len() < width {
+        true => {
+            let mut result = bytes::BytesMut::with_capacity(width);
+            result.extend_from_slice(&value);
+            zero_pad_mut(&mut result, width);
+            result.freeze()
+        },
+        false => value,
+    }
+}
+
+#[derive(Clone, Debug)]
+///Header value template with single placeholder for ID.
+pub struct Template {
+    prefix: bytes::Bytes,
+    suffix: bytes::Bytes,
+}
+
+impl Template {
+    ///Placeholder to be replaced with ID.
+    pub const PLACEHOLDER: &'static str = "{id}";
+
+    ///Parses template, splitting it by first placeholder.
+    ///
+    ///If there is no placeholder, then ID is appended to the end.
+    pub fn new(template: &str) -> Self {
+        let (prefix, suffix) = match template.split_once(Self::PLACEHOLDER) {
+            Some(parts) => parts,
+            None => (template, ""),
+        };
+
+        Self {
+            prefix: bytes::Bytes::copy_from_slice(prefix.as_bytes()),
+            suffix: bytes::Bytes::copy_from_slice(suffix.as_bytes()),
+        }
+    }
+
+    ///Renders template with provided ID.
+    pub fn render(&self, id: &[u8]) -> bytes::Bytes {
+        let mut result = bytes::BytesMut::with_capacity(self.prefix.len() + id.len() + self.suffix.len());
+        result.extend_from_slice(&self.prefix);
+        result.extend_from_slice(id);
+        result.extend_from_slice(&self.suffix);
+        result.freeze()
+    }
+}
+
+///Finds value of first query parameter with specified `name`.
+///
+///Value is returned as it is, without percent decoding.
+pub fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| match pair.split_once('=') {
+        Some((key, value)) if key == name => Some(value),
+        Some(_) => None,
+        None if pair == name => Some(""),
+        None => None,
+    })
+}
+
+#[cfg(any(feature = "random", feature = "rand", feature = "hash"))]
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+#[cfg(any(feature = "random", feature = "rand", feature = "hash"))]
+///Encodes `input` as lower case hex into `out`, which must be twice as long as `input`.
+pub fn hex_encode(input: &[u8], out: &mut [u8]) {
+    debug_assert_eq!(input.len() * 2, out.len());
+    for (byte, out) in input.iter().zip(out.chunks_exact_mut(2)) {
+        out[0] = HEX[(byte >> 4) as usize];
+        out[1] = HEX[(byte & 0xf) as usize];
+    }
+}
+
+#[cfg(any(feature = "random", feature = "rand"))]
+///Encodes `input` as lower case hex string.
+pub fn hex_string(input: &[u8]) -> alloc::string::String {
+    let mut result = alloc::vec![0u8; input.len() * 2];
+    hex_encode(input, &mut result);
+    //Safety: only hex digits are written
+    unsafe {
+        alloc::string::String::from_utf8_unchecked(result)
+    }
+}
+
+#[cfg(feature = "getrandom")]
+//Counter of fallback random values, independent from counters of particular generators.
+static FALLBACK_COUNTER: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(0);
+
+#[cfg(any(feature = "random", feature = "b3", feature = "amzn"))]
+#[inline]
+///Fills `bytes` using OS RNG, falling back to pseudo random bytes if it is not available.
+///
+///Fallback is derived from current time (with `std`), process id and counter.
+///It is unique within process, but its uniqueness across processes is weaker.
+pub fn fill_random(bytes: &mut [u8]) {
+    fill_random_with(bytes, getrandom::getrandom)
+}
+
+#[cfg(feature = "getrandom")]
+pub fn fill_random_with(bytes: &mut [u8], fill: fn(&mut [u8]) -> Result<(), getrandom::Error>) {
+    if fill(bytes).is_err() {
+        fallback_random(bytes);
+    }
+}
+
+#[cfg(feature = "getrandom")]
+fn fallback_seed() -> u64 {
+    #[cfg(feature = "std")]
+    {
+        let nanos = match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+            Ok(time) => time.as_nanos() as u64,
+            Err(_) => 0,
+        };
+        nanos ^ ((std::process::id() as u64) << 32)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        //Address of static differs between runs when ASLR is enabled.
+        &FALLBACK_COUNTER as *const _ as u64
+    }
+}
+
+#[cfg(feature = "getrandom")]
+//SplitMix64 over seed and counter, which mixes state bijectively.
+fn fallback_random(bytes: &mut [u8]) {
+    const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    let counter = FALLBACK_COUNTER.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+    let mut state = fallback_seed() ^ counter.wrapping_mul(GOLDEN_GAMMA);
+    for chunk in bytes.chunks_mut(8) {
+        state = state.wrapping_add(GOLDEN_GAMMA);
+        let mut value = state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^= value >> 31;
+        chunk.copy_from_slice(&value.to_le_bytes()[..chunk.len()]);
+    }
+}
+
+#[cfg(any(feature = "b3", feature = "amzn"))]
+///Parses lower case hex of exact length.
+pub fn parse_hex(text: &str, len: usize) -> Option<u128> {
+    if text.len() != len || !text.bytes().all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte)) {
+        return None;
+    }
+
+    u128::from_str_radix(text, 16).ok()
+}
+
+#[inline(always)]
+fn base64_digit(byte: u8) -> Option<u32> {
+    match byte {
+        b'A'..=b'Z' => Some((byte - b'A') as u32),
+        b'a'..=b'z' => Some((byte - b'a' + 26) as u32),
+        b'0'..=b'9' => Some((byte - b'0' + 52) as u32),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+///Decodes standard base64, with or without padding.
+pub fn base64_decode(value: &[u8]) -> Option<alloc::vec::Vec<u8>> {
+    let value = match value {
+        [rest @ .., b'=', b'='] | [rest @ .., b'='] if value.len() % 4 == 0 => rest,
+        _ => value,
+    };
+    if value.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut result = alloc::vec::Vec::with_capacity(value.len() * 3 / 4);
+    for chunk in value.chunks(4) {
+        let mut acc = 0u32;
+        for byte in chunk.iter().copied() {
+            acc = (acc << 6) | base64_digit(byte)?;
+        }
+        acc <<= 6 * (4 - chunk.len()) as u32;
+
+        let bytes = acc.to_be_bytes();
+        result.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+
+    Some(result)
+}
+
+#[inline(always)]
+fn is_tchar(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+//Parses parameters of structured field item, which are ignored.
+fn sf_params(mut rest: &str) -> Option<()> {
+    while let Some(param) = rest.strip_prefix(';') {
+        let param = param.trim_start_matches(' ');
+        let key_len = param.bytes().take_while(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || b"_-.*".contains(byte)).count();
+        match param.as_bytes().first() {
+            Some(first) if first.is_ascii_lowercase() || *first == b'*' => (),
+            _ => return None,
+        }
+        rest = &param[key_len..];
+        if let Some(value) = rest.strip_prefix('=') {
+            let (_, value_rest) = sf_bare_item(value)?;
+            rest = value_rest;
+        }
+    }
+
+    match rest.is_empty() {
+        true => Some(()),
+        false => None,
+    }
+}
+
+//Parses sf-string, sf-token or sf-integer, returning its textual value and remaining input.
+fn sf_bare_item(value: &str) -> Option<(alloc::borrow::Cow<'_, str>, &str)> {
+    let bytes = value.as_bytes();
+    match bytes.first()? {
+        b'"' => {
+            let mut result = alloc::string::String::new();
+            let mut escaped = false;
+            for (idx, byte) in bytes.iter().copied().enumerate().skip(1) {
+                match (escaped, byte) {
+                    (false, b'\\') => escaped = true,
+                    (false, b'"') => return Some((result.into(), &value[idx + 1..])),
+                    (true, b'"') | (true, b'\\') | (false, 0x20..=0x7e) => {
+                        escaped = false;
+                        result.push(byte as char);
+                    },
+                    _ => return None,
+                }
+            }
+            None
+        },
+        first if first.is_ascii_alphabetic() || *first == b'*' => {
+            let len = bytes.iter().take_while(|byte| is_tchar(**byte) || **byte == b':' || **byte == b'/').count();
+            Some((value[..len].into(), &value[len..]))
+        },
+        first if first.is_ascii_digit() || *first == b'-' => {
+            let len = 1 + bytes[1..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+            match len > 15 || (*first == b'-' && len == 1) || bytes.get(len) == Some(&b'.') {
+                true => None,
+                false => Some((value[..len].into(), &value[len..])),
+            }
+        },
+        _ => None,
+    }
+}
+
+///Parses structured field item (RFC 8941), returning value of string, token or integer.
+///
+///Parameters are validated, but ignored.
+pub fn sf_item(value: &str) -> Option<alloc::borrow::Cow<'_, str>> {
+    let (item, rest) = sf_bare_item(value)?;
+    sf_params(rest)?;
+    Some(item)
+}
+
+///Serializes value as structured field string, returning `None` if it contains characters outside of visible ASCII range.
+pub fn sf_string(value: &[u8]) -> Option<bytes::Bytes> {
+    let mut result = bytes::BytesMut::with_capacity(value.len() + 2);
+    result.extend_from_slice(b"\"");
+    for byte in value.iter().copied() {
+        match byte {
+            b'"' | b'\\' => result.extend_from_slice(&[b'\\', byte]),
+            0x20..=0x7e => result.extend_from_slice(&[byte]),
+            _ => return None,
+        }
+    }
+    result.extend_from_slice(b"\"");
+    Some(result.freeze())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+    #[test]
+    fn verify_writer_capacity_fits_uuid() {
+        let mut writer = BytesWriter::with_capacity(UUID.len());
+        let ptr = writer.buf.as_ptr();
+        fmt::Write::write_str(&mut writer, UUID).unwrap();
+        assert_eq!(writer.buf.capacity(), UUID.len());
+        assert_eq!(writer.buf.as_ptr(), ptr);
+        assert_eq!(writer.freeze(), UUID);
+
+        let mut writer = BytesWriter::with_capacity(crate::DEFAULT_LEN_HINT);
+        fmt::Write::write_str(&mut writer, UUID).unwrap();
+        assert!(writer.buf.capacity() > crate::DEFAULT_LEN_HINT);
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn verify_fill_random_fallbacks_when_rng_fails() {
+        fn failing(_: &mut [u8]) -> Result<(), getrandom::Error> {
+            Err(getrandom::Error::UNSUPPORTED)
+        }
+
+        let mut first = [0u8; 12];
+        let mut second = [0u8; 12];
+        fill_random_with(&mut first, failing);
+        fill_random_with(&mut second, failing);
+        assert_ne!(first, [0; 12]);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn verify_zero_pad() {
+        assert_eq!(zero_pad(bytes::Bytes::from_static(b"42"), 5), "00042");
+        assert_eq!(zero_pad(bytes::Bytes::from_static(b"-42"), 5), "-0042");
+        assert_eq!(zero_pad(bytes::Bytes::from_static(b"-42"), 3), "-42");
+        assert_eq!(zero_pad(bytes::Bytes::from_static(b"12345"), 3), "12345");
+        assert_eq!(zero_pad(bytes::Bytes::from_static(b"-"), 3), "-00");
+        assert_eq!(zero_pad(bytes::Bytes::new(), 2), "00");
+
+        //Padding within capacity doesn't re-allocate
+        let mut writer = BytesWriter::with_capacity(10);
+        let ptr = writer.buf.as_ptr();
+        fmt::Write::write_str(&mut writer, "-7").unwrap();
+        writer.zero_pad(10);
+        assert_eq!(writer.buf.as_ptr(), ptr);
+        assert_eq!(writer.freeze(), "-000000007");
+    }
+
+    #[test]
+    fn verify_sf_item() {
+        assert_eq!(sf_item("\"abc\"").as_deref(), Some("abc"));
+        assert_eq!(sf_item("\"a\\\"b\\\\c\"").as_deref(), Some("a\"b\\c"));
+        assert_eq!(sf_item("token/1:2").as_deref(), Some("token/1:2"));
+        assert_eq!(sf_item("-42").as_deref(), Some("-42"));
+        assert_eq!(sf_item("\"abc\";origin=edge;sampled").as_deref(), Some("abc"));
+        assert_eq!(sf_item("\"abc\";origin=\"edge\"").as_deref(), Some("abc"));
+
+        assert!(sf_item("\"abc").is_none());
+        assert!(sf_item("\"abc\" def").is_none());
+        assert!(sf_item("\"a\\b\"").is_none());
+        assert!(sf_item("abc,def").is_none());
+        assert!(sf_item("1.5").is_none());
+        assert!(sf_item("\"abc\";Bad=1").is_none());
+        assert!(sf_item("(abc)").is_none());
+        assert!(sf_item("").is_none());
+    }
+
+    #[test]
+    fn verify_sf_string() {
+        assert_eq!(sf_string(b"abc").unwrap(), "\"abc\"");
+        assert_eq!(sf_string(b"a\"b\\c").unwrap(), "\"a\\\"b\\\\c\"");
+        assert!(sf_string("é".as_bytes()).is_none());
+    }
+}
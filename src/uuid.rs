@@ -1,29 +1,51 @@
 pub use lolid::Uuid;
+pub use lolid::Version as UuidVersion;
 use lolid::Timestamp;
+use crate::utils;
 
 use core::sync::atomic::{AtomicU32, Ordering};
+use alloc::sync::Arc;
 
-fn v1(mac: [u8; 6]) -> Uuid {
-    ///Extra guarantee that v1 is unique.
-    ///u32 should take a while to repeat itself.
-    static COUNTER: AtomicU32 = AtomicU32::new(1);
-    let counter = (COUNTER.fetch_add(1, Ordering::SeqCst) & 0xffff) as u16;
+///Extra guarantee that v1 is unique.
+///u32 should take a while to repeat itself.
+static COUNTER: AtomicU32 = AtomicU32::new(1);
 
-    Uuid::v1(Timestamp::now().set_counter(counter), mac)
+///RFC 4122 `clock_seq` is 14 bits, the rest of its octets is occupied by variant.
+const CLOCK_SEQ_MASK: u32 = 0x3FFF;
+
+#[derive(Clone, Copy)]
+enum Counter {
+    Shared,
+    Local(&'static AtomicU32),
 }
 
-fn v4(_: [u8; 6]) -> Uuid {
-    Uuid::v4()
+impl Counter {
+    #[inline(always)]
+    fn next(&self) -> u16 {
+        match self {
+            Self::Shared => next_clock_seq(&COUNTER),
+            Self::Local(counter) => next_clock_seq(counter),
+        }
+    }
 }
 
-const V1: fn([u8; 6]) -> Uuid = v1;
-const V4: fn([u8; 6]) -> Uuid = v4;
+#[inline(always)]
+///Returns next value of `clock_seq`, wrapping around within its 14 bits.
+fn next_clock_seq(counter: &AtomicU32) -> u16 {
+    (counter.fetch_add(1, Ordering::SeqCst) & CLOCK_SEQ_MASK) as u16
+}
 
 #[derive(Copy, Clone)]
 ///Generator which by default uses `v1` and fallbacks to `v4` if mac address is unknown
+///
+///`v1` relies on counter to differentiate UUIDs generated within the same timestamp.
+///By default counter is shared process-wide, giving the best uniqueness guarantee across all generators on the same host.
+///Alternatively generator can use own counter, which isolates its sequence from others at the cost of
+///potential collisions with other generators, using the same mac address.
 pub struct UuidGenerator {
     mac: [u8; 6],
-    gen: fn([u8; 6]) -> Uuid
+    is_v1: bool,
+    counter: Counter,
 }
 
 impl UuidGenerator {
@@ -31,7 +53,8 @@ impl UuidGenerator {
     pub const fn new_v4() -> Self {
         Self {
             mac: [0; 6],
-            gen: V4,
+            is_v1: false,
+            counter: Counter::Shared,
         }
     }
 
@@ -40,38 +63,196 @@ impl UuidGenerator {
     ///If mac address is available, generator will use `uuid` v1.
     ///Otherwise it defaults to `v4`
     ///
+    ///Mac address is looked up only once per process, and then re-used by subsequent calls.
+    ///
     ///In case that is not desirable please use `new_v4` to only use random generator.
     pub fn new() -> Self {
-        let (mac, gen) = match mac_address::get_mac_address() {
-            Ok(Some(addr)) => (addr.bytes(), V1),
+        static CACHE: std::sync::OnceLock<UuidGenerator> = std::sync::OnceLock::new();
+
+        *CACHE.get_or_init(Self::new_uncached)
+    }
+
+    #[inline(always)]
+    ///Creates new instance, which uses process-wide counter for `v1`.
+    ///
+    ///This is the same as `new`.
+    pub fn with_shared_counter() -> Self {
+        Self::new()
+    }
+
+    ///Creates new instance, which uses specified `counter` for `v1`, shared only with generators using the same counter.
+    ///
+    ///Counter is `'static`, so that generator remains `Copy`, hence it is usually declared as `static`.
+    ///Use `LocalUuidGenerator` to allocate counter instead:
+    ///
+    ///```rust
+    ///use core::sync::atomic::AtomicU32;
+    ///use tower_http_req_id::UuidGenerator;
+    ///
+    ///static COUNTER: AtomicU32 = AtomicU32::new(1);
+    ///let gen = UuidGenerator::with_local_counter(&COUNTER);
+    ///```
+    ///
+    ///Refer to `new` for details.
+    pub fn with_local_counter(counter: &'static AtomicU32) -> Self {
+        let mut result = Self::new();
+        result.counter = Counter::Local(counter);
+        result
+    }
+
+    ///Creates new instance, looking up mac address without using process-wide cache.
+    ///
+    ///Refer to `new` for details.
+    pub fn new_uncached() -> Self {
+        let (mac, is_v1) = match mac_address::get_mac_address() {
+            Ok(Some(addr)) => (addr.bytes(), true),
             //It is generally ok to use v4 as it is unique enough
-            _ => ([0; 6], V4)
+            _ => ([0; 6], false)
         };
 
         Self {
             mac,
-            gen
+            is_v1,
+            counter: Counter::Shared,
+        }
+    }
+
+    #[inline(always)]
+    ///Returns whether generate is able to use `v1`
+    pub fn is_v1(&self) -> bool {
+        self.is_v1
+    }
+
+    #[inline(always)]
+    ///Creates `v1` timestamp.
+    ///
+    ///Ticks have 100ns resolution, hence calls within the same window get identical ticks
+    ///and only counter differentiates them.
+    fn timestamp(&self) -> Timestamp {
+        Timestamp::now().set_counter(self.counter.next())
+    }
+
+    #[inline(always)]
+    ///Generates `UUID`
+    ///
+    ///If OS RNG is not available for `v4`, then it fallbacks to pseudo random `UUID`,
+    ///derived from current time, process id and counter.
+    ///Such `UUID` is unique within process, but its uniqueness across processes is weaker.
+    pub fn gen(&self) -> Uuid {
+        self.gen_with(|| self.timestamp())
+    }
+
+    #[inline(always)]
+    //Generates `UUID`, using `timestamp` for `v1`.
+    fn gen_with(&self, timestamp: impl FnOnce() -> Timestamp) -> Uuid {
+        match self.is_v1 {
+            true => Uuid::v1(timestamp(), self.mac),
+            false => v4(getrandom::getrandom),
+        }
+    }
+}
+
+#[derive(Clone)]
+///Generator, which is the same as `UuidGenerator`, but uses own counter for `v1`, shared only with its clones.
+///
+///Unlike `UuidGenerator::with_local_counter`, counter is not required to be `'static`, at the cost of generator not being `Copy`.
+pub struct LocalUuidGenerator {
+    inner: UuidGenerator,
+    counter: Arc<AtomicU32>,
+}
+
+impl LocalUuidGenerator {
+    #[inline]
+    ///Creates new instance with newly allocated counter.
+    ///
+    ///Refer to `UuidGenerator::new` for details.
+    pub fn new() -> Self {
+        Self::with_counter(Arc::new(AtomicU32::new(1)))
+    }
+
+    #[inline]
+    ///Creates new instance, which uses specified `counter` for `v1`, shared only with generators using the same counter.
+    ///
+    ///Refer to `UuidGenerator::new` for details.
+    pub fn with_counter(counter: Arc<AtomicU32>) -> Self {
+        Self {
+            inner: UuidGenerator::new(),
+            counter,
         }
     }
 
     #[inline(always)]
     ///Returns whether generate is able to use `v1`
     pub fn is_v1(&self) -> bool {
-        self.gen == V1
+        self.inner.is_v1
     }
 
     #[inline(always)]
     ///Generates `UUID`
+    ///
+    ///Refer to `UuidGenerator::gen` for details.
     pub fn gen(&self) -> Uuid {
-        (self.gen)(self.mac)
+        self.inner.gen_with(|| Timestamp::now().set_counter(next_clock_seq(&self.counter)))
     }
 }
 
+impl Default for LocalUuidGenerator {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn v4(fill: fn(&mut [u8]) -> Result<(), getrandom::Error>) -> Uuid {
+    let mut bytes = [0; 16];
+    utils::fill_random_with(&mut bytes, fill);
+    Uuid::v4_from(bytes)
+}
+
+///Length of hyphenated `UUID` string.
+const UUID_LEN: usize = 36;
+
 impl super::IdGen<Uuid> for UuidGenerator {
     #[inline(always)]
     fn gen(&self) -> Uuid {
         Self::gen(self)
     }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        UUID_LEN
+    }
+
+    #[inline(always)]
+    fn header_value_fn(&self) -> Option<fn(&Uuid) -> Option<http::HeaderValue>> {
+        Some(header_value)
+    }
+}
+
+//Encodes into stack buffer, bypassing formatting machinery.
+fn header_value(id: &Uuid) -> Option<http::HeaderValue> {
+    let value = bytes::Bytes::copy_from_slice(id.to_str().as_bytes());
+    //Safety: hyphenated UUID consists of hex digits and hyphens only, which are valid within header value.
+    Some(unsafe {
+        http::HeaderValue::from_maybe_shared_unchecked(value)
+    })
+}
+
+impl super::IdGen<Uuid> for LocalUuidGenerator {
+    #[inline(always)]
+    fn gen(&self) -> Uuid {
+        Self::gen(self)
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        UUID_LEN
+    }
+
+    #[inline(always)]
+    fn header_value_fn(&self) -> Option<fn(&Uuid) -> Option<http::HeaderValue>> {
+        Some(header_value)
+    }
 }
 
 impl super::IdGen<alloc::string::String> for UuidGenerator {
@@ -79,6 +260,49 @@ impl super::IdGen<alloc::string::String> for UuidGenerator {
     fn gen(&self) -> alloc::string::String {
         alloc::format!("{}", Self::gen(self))
     }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        UUID_LEN
+    }
+}
+
+impl super::IdGen<alloc::string::String> for LocalUuidGenerator {
+    #[inline(always)]
+    fn gen(&self) -> alloc::string::String {
+        alloc::format!("{}", Self::gen(self))
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        UUID_LEN
+    }
+}
+
+//Parses UUID, accepting only specified version.
+fn parse_version<const V: u8>(text: &str) -> Option<Uuid> {
+    match Uuid::parse_str(text) {
+        Ok(uuid) if uuid.as_bytes()[6] >> 4 == V => Some(uuid),
+        _ => None,
+    }
+}
+
+impl<G> super::GenerateRequestIdLayer<G, Uuid> {
+    #[inline(always)]
+    ///Accepts UUID from client only if it is of specified `version`, otherwise new ID is generated.
+    ///
+    ///This is implemented via `parse_with`, hence it replaces any parser specified before.
+    pub const fn require_uuid_version(self, version: UuidVersion) -> Self {
+        let parse: fn(&str) -> Option<Uuid> = match version {
+            UuidVersion::Nil => parse_version::<0>,
+            UuidVersion::Mac => parse_version::<1>,
+            UuidVersion::Dce => parse_version::<2>,
+            UuidVersion::Md5 => parse_version::<3>,
+            UuidVersion::Random => parse_version::<4>,
+            UuidVersion::Sha1 => parse_version::<5>,
+        };
+        self.parse_with(parse)
+    }
 }
 
 #[cfg(test)]
@@ -96,6 +320,126 @@ mod tests {
         assert_eq!(expected, generator.is_v1());
     }
 
+    #[test]
+    fn verify_new_uses_cached_mac() {
+        let expected = UuidGenerator::new_uncached();
+        for _ in 0..10 {
+            let generator = UuidGenerator::new();
+            assert_eq!(expected.is_v1(), generator.is_v1());
+            assert_eq!(expected.mac, generator.mac);
+        }
+    }
+
+    #[test]
+    fn verify_v4_fallbacks_when_rng_fails() {
+        fn failing(_: &mut [u8]) -> Result<(), getrandom::Error> {
+            Err(getrandom::Error::UNSUPPORTED)
+        }
+
+        let first = v4(failing);
+        let second = v4(failing);
+        assert!(first.is_version(lolid::Version::Random));
+        assert!(first.is_variant());
+        assert_ne!(first, second);
+        assert_eq!(first.to_str().len(), UUID_LEN);
+    }
+
+    fn counter(uuid: Uuid) -> u16 {
+        let bytes = uuid.bytes();
+        (((bytes[8] & 0x3f) as u16) << 8) | bytes[9] as u16
+    }
+
+    #[test]
+    fn verify_local_counter_is_independent() {
+        static FIRST: AtomicU32 = AtomicU32::new(1);
+        static SECOND: AtomicU32 = AtomicU32::new(1);
+
+        let first = UuidGenerator::with_local_counter(&FIRST);
+        if !first.is_v1() {
+            return;
+        }
+        let second = UuidGenerator::with_local_counter(&SECOND);
+        let first_clone = first;
+
+        assert_eq!(counter(first.gen()), 1);
+        assert_eq!(counter(first.gen()), 2);
+        assert_eq!(counter(second.gen()), 1);
+        assert_eq!(counter(first_clone.gen()), 3);
+        assert_eq!(counter(second.gen()), 2);
+    }
+
+    #[test]
+    fn verify_allocated_local_counter_is_shared_by_clones_only() {
+        let first = LocalUuidGenerator::new();
+        if !first.is_v1() {
+            return;
+        }
+        let second = LocalUuidGenerator::new();
+        let first_clone = first.clone();
+
+        assert_eq!(counter(first.gen()), 1);
+        assert_eq!(counter(first.gen()), 2);
+        assert_eq!(counter(second.gen()), 1);
+        assert_eq!(counter(first_clone.gen()), 3);
+
+        let counter_ref = Arc::new(AtomicU32::new(10));
+        let third = LocalUuidGenerator::with_counter(counter_ref.clone());
+        assert_eq!(counter(third.gen()), 10);
+        assert_eq!(counter_ref.load(Ordering::SeqCst), 11);
+    }
+
+    #[test]
+    fn verify_shared_counter_is_interleaved() {
+        let first = UuidGenerator::with_shared_counter();
+        if !first.is_v1() {
+            return;
+        }
+        let second = UuidGenerator::with_shared_counter();
+
+        //Other tests may use shared counter concurrently, so only order is guaranteed.
+        //Counter wraps within clock_seq, hence order is checked by distance modulo its range.
+        let distance = |from: u16, to: u16| to.wrapping_sub(from) & CLOCK_SEQ_MASK as u16;
+        let first_counter = counter(first.gen());
+        let second_counter = counter(second.gen());
+        let last_counter = counter(first.gen());
+        assert!(distance(first_counter, second_counter) > 0);
+        assert!(distance(first_counter, second_counter) < distance(first_counter, last_counter));
+    }
+
+    #[test]
+    fn verify_v1_timestamp_is_unique_within_clock_seq() {
+        static COUNTER: AtomicU32 = AtomicU32::new(1);
+        let generator = UuidGenerator::with_local_counter(&COUNTER);
+
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..10_000 {
+            let (ticks, counter) = generator.timestamp().into_parts();
+            assert!(u32::from(counter) <= CLOCK_SEQ_MASK);
+            assert!(seen.insert((ticks, counter)), "duplicate timestamp ({}, {})", ticks, counter);
+        }
+    }
+
+    #[test]
+    fn verify_counter_wraps_within_clock_seq() {
+        static LOCAL: AtomicU32 = AtomicU32::new(CLOCK_SEQ_MASK);
+        static GENERATOR: AtomicU32 = AtomicU32::new(CLOCK_SEQ_MASK);
+
+        let local = Counter::Local(&LOCAL);
+        assert_eq!(local.next(), 0x3FFF);
+        assert_eq!(local.next(), 0);
+        assert_eq!(local.next(), 1);
+
+        let generator = UuidGenerator {
+            mac: [1, 2, 3, 4, 5, 6],
+            is_v1: true,
+            counter: Counter::Local(&GENERATOR),
+        };
+        let uuid = generator.gen();
+        assert!(uuid.is_variant());
+        assert_eq!(counter(uuid), 0x3FFF);
+        assert_eq!(counter(generator.gen()), 0);
+    }
+
     #[test]
     fn should_generate_unique_uuid() {
         let uuid = UuidGenerator::new();
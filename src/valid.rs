@@ -0,0 +1,160 @@
+use core::fmt;
+
+use crate::{utils, InvalidValue};
+
+///Marker of ID type, which textual representation is always valid header value.
+///
+///This allows middleware to skip validation of header value, when enabled via `GenerateRequestIdLayer::assume_valid_header`.
+///
+///## Safety
+///
+///`Display` implementation must only produce visible ASCII characters (`0x21..=0x7E`).
+pub unsafe trait AlwaysValidHeader: fmt::Display {
+    #[inline]
+    ///Creates header value, left padded with zeroes up to `pad_width`.
+    ///
+    ///Zeroes are placed after leading minus sign, the same way as for IDs, which are not `AlwaysValidHeader`.
+    ///
+    ///Default implementation formats ID via `Display` into buffer of `len_hint` capacity.
+    ///Integers write their digits into stack buffer instead, allocating only header value itself.
+    fn to_header_value(&self, len_hint: usize, pad_width: usize) -> http::HeaderValue {
+        format_display(self, len_hint, pad_width)
+    }
+}
+
+fn format_display<T: AlwaysValidHeader + ?Sized>(id: &T, len_hint: usize, pad_width: usize) -> http::HeaderValue {
+    let mut header_value = utils::BytesWriter::with_capacity(len_hint);
+    let _ = fmt::Write::write_fmt(&mut header_value, format_args!("{}", id));
+    header_value.zero_pad(pad_width);
+
+    //Padding with zeroes cannot make value invalid
+    //Safety: AlwaysValidHeader guarantees only visible ASCII characters are written
+    unsafe {
+        http::HeaderValue::from_maybe_shared_unchecked(header_value.freeze())
+    }
+}
+
+//Enough for i128::MIN
+const INT_BUF_LEN: usize = 40;
+
+//Writes integer into stack buffer, returning `None` if padding doesn't fit.
+//
+//Zeroes are placed after minus sign, which counts towards `pad_width`, just like `utils::zero_pad` does.
+fn int_header_value(is_negative: bool, num: u128, pad_width: usize) -> Option<http::HeaderValue> {
+    if pad_width > INT_BUF_LEN {
+        return None;
+    }
+
+    let mut buf = [b'0'; INT_BUF_LEN];
+    let mut idx = buf.len();
+    //Narrow division is considerably faster
+    if num <= u64::MAX as u128 {
+        let mut num = num as u64;
+        loop {
+            idx -= 1;
+            buf[idx] = b'0' + (num % 10) as u8;
+            num /= 10;
+            if num == 0 {
+                break;
+            }
+        }
+    } else {
+        let mut num = num;
+        loop {
+            idx -= 1;
+            buf[idx] = b'0' + (num % 10) as u8;
+            num /= 10;
+            if num == 0 {
+                break;
+            }
+        }
+    }
+    //Buffer is filled with zeroes, so padding is just extension of the slice.
+    let mut start = core::cmp::min(idx, buf.len() - pad_width.saturating_sub(is_negative as usize));
+    if is_negative {
+        start -= 1;
+        buf[start] = b'-';
+    }
+    //Safety: only digits and minus sign are written
+    Some(unsafe {
+        http::HeaderValue::from_maybe_shared_unchecked(bytes::Bytes::copy_from_slice(&buf[start..]))
+    })
+}
+
+macro_rules! impl_always_valid_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            unsafe impl AlwaysValidHeader for $ty {
+                #[inline]
+                fn to_header_value(&self, len_hint: usize, pad_width: usize) -> http::HeaderValue {
+                    match int_header_value(false, *self as u128, pad_width) {
+                        Some(header_value) => header_value,
+                        None => format_display(self, len_hint, pad_width),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_always_valid_signed {
+    ($($ty:ty),*) => {
+        $(
+            unsafe impl AlwaysValidHeader for $ty {
+                #[inline]
+                fn to_header_value(&self, len_hint: usize, pad_width: usize) -> http::HeaderValue {
+                    match int_header_value(*self < 0, self.unsigned_abs() as u128, pad_width) {
+                        Some(header_value) => header_value,
+                        None => format_display(self, len_hint, pad_width),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_always_valid_non_zero {
+    ($($ty:ty),*) => {
+        $(
+            unsafe impl AlwaysValidHeader for $ty {
+                #[inline]
+                fn to_header_value(&self, len_hint: usize, pad_width: usize) -> http::HeaderValue {
+                    self.get().to_header_value(len_hint, pad_width)
+                }
+            }
+        )*
+    };
+}
+
+impl_always_valid_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_always_valid_signed!(i8, i16, i32, i64, i128, isize);
+impl_always_valid_non_zero!(core::num::NonZeroU8, core::num::NonZeroU16, core::num::NonZeroU32, core::num::NonZeroU64, core::num::NonZeroU128, core::num::NonZeroUsize);
+
+#[cfg(feature = "uuid")]
+unsafe impl AlwaysValidHeader for crate::Uuid {
+}
+
+#[inline]
+pub(crate) fn format_valid_header_value<T: AlwaysValidHeader>(id: &T, len_hint: usize, pad_width: usize, _: InvalidValue) -> Option<http::HeaderValue> {
+    Some(id.to_header_value(len_hint, pad_width))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_write_integer_header_value() {
+        assert_eq!(0u8.to_header_value(0, 0), "0");
+        assert_eq!(42u64.to_header_value(0, 5), "00042");
+        assert_eq!((-42i32).to_header_value(0, 5), "-0042");
+        assert_eq!((-42i32).to_header_value(0, 2), "-42");
+        assert_eq!((-1i8).to_header_value(0, 40), alloc::format!("{:040}", -1));
+        assert_eq!(u128::MAX.to_header_value(0, 0), alloc::format!("{}", u128::MAX));
+        assert_eq!(i128::MIN.to_header_value(0, 0), alloc::format!("{}", i128::MIN));
+        assert_eq!(core::num::NonZeroU32::new(7).unwrap().to_header_value(0, 2), "07");
+        //Padding exceeding stack buffer fallbacks to formatting
+        assert_eq!(1u8.to_header_value(0, 41), alloc::format!("{:0>41}", 1));
+        assert_eq!((-1i8).to_header_value(0, 41), alloc::format!("{:041}", -1));
+    }
+}
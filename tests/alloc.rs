@@ -0,0 +1,232 @@
+use tower_http_req_id::{IdGen, IdType, GenerateRequestId};
+
+use core::cell::Cell;
+use core::pin::pin;
+use core::future::{Future, ready};
+use core::convert::Infallible;
+use core::task::{self, RawWaker, RawWakerVTable, Waker};
+use std::alloc::{GlobalAlloc, Layout, System};
+
+use http::{Request, Response};
+use tower::{service_fn, Service};
+
+const TEST_ID: &str = "0123456789abcdef0123456789abcdef0123";
+
+//Same as `Waker::noop`, which requires newer Rust than `rust-version`.
+const NOOP_RAW_WAKER: RawWaker = RawWaker::new(core::ptr::null(), &NOOP_VTABLE);
+const NOOP_VTABLE: RawWakerVTable = RawWakerVTable::new(|_| NOOP_RAW_WAKER, |_| {}, |_| {}, |_| {});
+
+fn noop_waker() -> Waker {
+    //Safety: vtable functions do nothing, hence contract is upheld trivially.
+    unsafe {
+        Waker::from_raw(NOOP_RAW_WAKER)
+    }
+}
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.with(|allocs| allocs.set(allocs.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.with(|allocs| allocs.set(allocs.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocs() -> usize {
+    ALLOCS.with(|allocs| allocs.get())
+}
+
+#[derive(Clone)]
+struct TestGenerator {
+    len_hint: usize,
+}
+
+impl IdGen<String> for TestGenerator {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        TEST_ID.to_owned()
+    }
+
+    #[inline(always)]
+    fn len_hint(&self) -> usize {
+        self.len_hint
+    }
+}
+
+///Returns number of allocations performed while polling response future.
+fn count_response_allocs<G: IdGen<O> + Clone + Send + Sync + 'static, O: IdType<G> + Send + Sync + 'static>(gen: G) -> usize {
+    let mut svc = GenerateRequestId::<_, _, O>::new(service_fn(|_: Request<()>| ready(Ok::<_, Infallible>(Response::new(())))), gen);
+    let waker = noop_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let fut = svc.call(Request::new(()));
+    let mut fut = pin!(fut);
+
+    let before = allocs();
+    let res = match fut.as_mut().poll(&mut ctx) {
+        task::Poll::Ready(res) => res.unwrap(),
+        task::Poll::Pending => unreachable!(),
+    };
+    let result = allocs() - before;
+
+    assert!(res.headers().contains_key("x-request-id"));
+    result
+}
+
+#[test]
+fn should_not_reallocate_header_value_with_len_hint() {
+    let exact = count_response_allocs::<_, String>(TestGenerator { len_hint: TEST_ID.len() });
+    let small = count_response_allocs::<_, String>(TestGenerator { len_hint: 10 });
+    assert!(exact < small, "len_hint={} allocs={}, len_hint=10 allocs={}", TEST_ID.len(), exact, small);
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn should_not_reallocate_uuid_header_value() {
+    use tower_http_req_id::{Uuid, UuidGenerator};
+
+    let gen = UuidGenerator::new_v4();
+    assert_eq!(gen.gen().to_string().len(), IdGen::<Uuid>::len_hint(&gen));
+    assert_eq!(gen.gen().to_string().len(), IdGen::<String>::len_hint(&gen));
+
+    let uuid = count_response_allocs::<_, Uuid>(gen);
+    let exact = count_response_allocs::<_, String>(TestGenerator { len_hint: TEST_ID.len() });
+    assert_eq!(uuid, exact);
+}
+
+#[test]
+fn should_not_allocate_response_header_for_static_id() {
+    use tower_http_req_id::{StaticId, StaticGenerator};
+
+    let mut svc = GenerateRequestId::<_, _, StaticId>::new(service_fn(|req: Request<()>| {
+        assert_eq!(req.extensions().get::<StaticId>().expect("required-id is not inserted").as_str(), "static-id");
+        //Reserve header map's storage beforehand to only account for header value.
+        let mut res = Response::new(());
+        *res.headers_mut() = http::HeaderMap::with_capacity(1);
+        ready(Ok::<_, Infallible>(res))
+    }), StaticGenerator::new("static-id"));
+    let waker = noop_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let mut req = Request::new(());
+    req.headers_mut().insert("x-request-id", http::HeaderValue::from_static("client"));
+    let fut = svc.call(req);
+    let mut fut = pin!(fut);
+
+    let before = allocs();
+    let res = match fut.as_mut().poll(&mut ctx) {
+        task::Poll::Ready(res) => res.unwrap(),
+        task::Poll::Pending => unreachable!(),
+    };
+    assert_eq!(allocs() - before, 0);
+    assert_eq!(res.headers().get("x-request-id").expect("to have request-id header"), "static-id");
+}
+
+#[test]
+fn should_clone_shared_id_without_allocation() {
+    use tower_http_req_id::{SharedId, SharedGenerator};
+
+    let mut svc = GenerateRequestId::<_, _, SharedId>::new(service_fn(|req: Request<()>| {
+        let id = req.extensions().get::<SharedId>().expect("required-id is not inserted");
+
+        let before = allocs();
+        let clones = [id.clone(), id.clone(), id.clone()];
+        assert_eq!(allocs() - before, 0);
+        assert!(clones.iter().all(|clone| clone.as_str() == TEST_ID));
+
+        ready(Ok::<_, Infallible>(Response::new(())))
+    }), SharedGenerator::new(TestGenerator { len_hint: TEST_ID.len() }));
+    let waker = noop_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let fut = svc.call(Request::new(()));
+    let mut fut = pin!(fut);
+    let res = match fut.as_mut().poll(&mut ctx) {
+        task::Poll::Ready(res) => res.unwrap(),
+        task::Poll::Pending => unreachable!(),
+    };
+    assert_eq!(res.headers().get("x-request-id").expect("to have request-id header"), TEST_ID);
+}
+
+#[test]
+fn should_allocate_only_header_value_for_integer_id() {
+    use tower_http_req_id::GenerateRequestIdLayer;
+    use tower::Layer;
+
+    #[derive(Clone)]
+    struct CounterGenerator;
+
+    impl IdGen<u64> for CounterGenerator {
+        #[inline(always)]
+        fn gen(&self) -> u64 {
+            u64::MAX
+        }
+
+        #[inline(always)]
+        fn len_hint(&self) -> usize {
+            //Too small, to force re-allocation when formatting
+            1
+        }
+    }
+
+    let count = |layer: GenerateRequestIdLayer<CounterGenerator, u64>| {
+        let mut svc = layer.layer(service_fn(|_: Request<()>| {
+            //Reserve header map's storage beforehand to only account for header value.
+            let mut res = Response::new(());
+            *res.headers_mut() = http::HeaderMap::with_capacity(1);
+            ready(Ok::<_, Infallible>(res))
+        }));
+        let waker = noop_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+        let fut = svc.call(Request::new(()));
+        let mut fut = pin!(fut);
+
+        let before = allocs();
+        let res = match fut.as_mut().poll(&mut ctx) {
+            task::Poll::Ready(res) => res.unwrap(),
+            task::Poll::Pending => unreachable!(),
+        };
+        let result = allocs() - before;
+        assert_eq!(res.headers().get("x-request-id").expect("to have request-id header"), &u64::MAX.to_string());
+        result
+    };
+
+    let formatted = count(GenerateRequestIdLayer::new(CounterGenerator));
+    let stack = count(GenerateRequestIdLayer::new(CounterGenerator).assume_valid_header());
+    assert_eq!(stack, 1);
+    assert!(stack < formatted, "stack allocs={}, formatted allocs={}", stack, formatted);
+}
+
+#[test]
+fn should_layer_and_clone_service_without_allocation() {
+    use tower_http_req_id::GenerateRequestIdLayer;
+    use tower::Layer;
+
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator { len_hint: TEST_ID.len() }).generate_for_methods(&[http::Method::POST, http::Method::from_bytes(b"CUSTOM").unwrap()])
+                                                                                                   .query_param("request_id")
+                                                                                                   .structured_header(http::HeaderName::from_static("x-trace"), "id={id}")
+                                                                                                   .count_generated();
+    let inner = service_fn(|_: Request<()>| ready(Ok::<_, Infallible>(Response::new(()))));
+
+    let before = allocs();
+    let svc = layer.layer(inner);
+    let clones = [svc.clone(), svc.clone(), svc.clone()];
+    assert_eq!(allocs() - before, 0);
+    drop(clones);
+}
@@ -0,0 +1,87 @@
+//Runs middleware without tokio, to make sure it stays runtime agnostic.
+
+use tower_http_req_id::{IdGen, GenerateRequestIdLayer};
+
+use core::pin::{pin, Pin};
+use core::future::Future;
+use core::convert::Infallible;
+use std::task;
+use std::sync::Arc;
+use std::thread::{self, Thread};
+
+use http::{Request, Response};
+use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+const HEADER_NAME: &str = "x-request-id";
+const TEST_ID: &str = "id";
+
+struct ThreadWaker(Thread);
+
+impl task::Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+//Minimal executor, parking current thread until future is woken.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut ctx = task::Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+
+    loop {
+        match fut.as_mut().poll(&mut ctx) {
+            task::Poll::Ready(result) => break result,
+            task::Poll::Pending => thread::park(),
+        }
+    }
+}
+
+//Future, which completes only after being woken from another thread.
+struct WakeFromThread {
+    is_woken: bool,
+}
+
+impl Future for WakeFromThread {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+        if self.is_woken {
+            return task::Poll::Ready(());
+        }
+
+        self.is_woken = true;
+        let waker = ctx.waker().clone();
+        thread::spawn(move || waker.wake());
+        task::Poll::Pending
+    }
+}
+
+#[derive(Clone)]
+struct TestGenerator;
+
+impl IdGen<String> for TestGenerator {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        TEST_ID.to_owned()
+    }
+}
+
+#[test]
+fn should_round_trip_id_without_tokio() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|req: Request<()>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted").to_owned();
+                                       WakeFromThread { is_woken: false }.await;
+                                       Ok::<_, Infallible>(Response::new(id))
+                                   }));
+
+    let res = block_on(svc.clone().oneshot(Request::new(()))).unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+
+    let req = Request::get("/").header(HEADER_NAME, "client").body(()).unwrap();
+    let res = block_on(svc.oneshot(req)).unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client");
+    assert_eq!(res.into_body(), "client");
+}
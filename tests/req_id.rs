@@ -78,7 +78,7 @@ async fn should_insert_uuid_as_string_id() {
 async fn should_not_insert_new_uuid_id_if_header_present() {
     use tower_http_req_id::{Uuid, UuidGenerator};
 
-    let id = Uuid::v4();
+    let id = UuidGenerator::new_v4().gen();
     let gen = UuidGenerator::new();
 
     let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, Uuid>::new(gen))
@@ -97,3 +97,2827 @@ async fn should_not_insert_new_uuid_id_if_header_present() {
     assert_eq!(id.to_str(), res.as_str());
 }
 
+
+#[derive(Clone, Default)]
+struct CounterGenerator(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+impl CounterGenerator {
+    fn count(&self) -> usize {
+        self.0.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+impl IdGen<String> for CounterGenerator {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        (self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1).to_string()
+    }
+}
+
+#[tokio::test]
+async fn should_use_pregenerated_id() {
+    use tower::{Layer, Service};
+
+    let gen = CounterGenerator::default();
+    let mut svc = GenerateRequestIdLayer::<_, String>::new(gen.clone()).pregenerate()
+                                                                       .layer(service_fn(|req: Request<Body>| async move {
+                                                                           let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                                                           Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                                                       }));
+
+    svc.ready().await.unwrap();
+    assert_eq!(gen.count(), 1);
+    let res = svc.call(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "1");
+    assert_eq!(gen.count(), 1);
+
+    svc.ready().await.unwrap();
+    assert_eq!(gen.count(), 2);
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.call(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client");
+    assert_eq!(res.into_body(), "client");
+
+    //Unused pre-generated id is kept for next call
+    svc.ready().await.unwrap();
+    assert_eq!(gen.count(), 2);
+    let res = svc.call(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.into_body(), "2");
+    assert_eq!(gen.count(), 2);
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn should_insert_id_via_boxed_layer() {
+    use tower::Layer;
+    use tower::util::BoxLayer;
+
+    let layer: BoxLayer<_, Request<Body>, Response<String>, Infallible> = GenerateRequestIdLayer::<_, String>::new(TestGenerator).into_boxed();
+    let svc = layer.layer(service_fn(|req: Request<Body>| async move {
+        let id = req.extensions().get::<String>().expect("required-id is not inserted");
+        Ok::<_, Infallible>(Response::new(id.to_owned()))
+    }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[derive(Clone)]
+struct ValueGenerator(&'static str);
+
+impl IdGen<String> for ValueGenerator {
+    #[inline(always)]
+    fn gen(&self) -> String {
+        self.0.to_owned()
+    }
+}
+
+#[tokio::test]
+async fn should_handle_unsafe_id_value() {
+    use tower_http_req_id::InvalidValue;
+
+    let handler = service_fn(|req: Request<Body>| async move {
+        let id = req.extensions().get::<String>().expect("required-id is not inserted");
+        Ok::<_, Infallible>(Response::new(id.to_owned()))
+    });
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(ValueGenerator("a b")))
+                                   .service(handler);
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "a b");
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(ValueGenerator("a b\t ")))
+                                   .service(handler);
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), "a b\t ");
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(ValueGenerator("a%b\x01 ")).on_invalid_value(InvalidValue::Encode))
+                                   .service(handler);
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "a%25b%01%20");
+    assert_eq!(res.into_body(), "a%b\x01 ");
+}
+
+#[tokio::test]
+async fn should_generate_id_for_configured_methods() {
+    use http::Method;
+
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator).generate_for_methods(&[Method::POST, Method::PUT, Method::DELETE]);
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let mut req = Request::post("/").body(Body::empty()).unwrap();
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+
+    let mut req = Request::get("/").body(Body::empty()).unwrap();
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client");
+    assert_eq!(res.into_body(), "client");
+}
+
+#[tokio::test]
+async fn should_poll_not_unpin_inner_future() {
+    use core::future::Future;
+    use core::marker::PhantomPinned;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+
+    struct YieldOnce {
+        id: String,
+        yielded: bool,
+        _pinned: PhantomPinned,
+    }
+
+    impl Future for YieldOnce {
+        type Output = Result<Response<String>, Infallible>;
+
+        fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+            //Safe as no field is structurally pinned.
+            let this = unsafe {
+                self.get_unchecked_mut()
+            };
+
+            if this.yielded {
+                Poll::Ready(Ok(Response::new(core::mem::take(&mut this.id))))
+            } else {
+                this.yielded = true;
+                ctx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|req: Request<Body>| {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted").to_owned();
+                                       let fut = YieldOnce {
+                                           id,
+                                           yielded: false,
+                                           _pinned: PhantomPinned,
+                                       };
+                                       async move {
+                                           tokio::task::yield_now().await;
+                                           fut.await
+                                       }
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_pass_through_when_disabled() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::disabled(TestGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       assert!(req.extensions().get::<String>().is_none());
+                                       let id = req.headers().get(HEADER_NAME).map(|id| id.to_str().unwrap().to_owned()).unwrap_or_default();
+                                       Ok::<_, Infallible>(Response::new(id))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), "");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), "client");
+}
+
+#[tokio::test]
+async fn should_zero_pad_integer_id() {
+    #[derive(Clone)]
+    struct IntGenerator;
+
+    impl IdGen<u64> for IntGenerator {
+        #[inline(always)]
+        fn gen(&self) -> u64 {
+            42
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, u64>::new(IntGenerator).zero_pad(10))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<u64>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(*id))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "0000000042");
+    assert_eq!(res.into_body(), 42);
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("0000000007"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "0000000007");
+    assert_eq!(res.into_body(), 7);
+
+    #[derive(Clone)]
+    struct NegativeGenerator;
+
+    impl IdGen<i64> for NegativeGenerator {
+        #[inline(always)]
+        fn gen(&self) -> i64 {
+            -42
+        }
+    }
+
+    //Zeros are placed after sign, hence padded value is still valid integer
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, i64>::new(NegativeGenerator).zero_pad(10))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<i64>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(*id))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    let header = res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap().to_owned();
+    assert_eq!(header, "-000000042");
+    assert_eq!(res.into_body(), -42);
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_str(&header).unwrap());
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), header);
+    assert_eq!(res.into_body(), -42);
+}
+
+#[tokio::test]
+async fn should_reuse_id_of_nested_layer() {
+    let gen = CounterGenerator::default();
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()))
+                                   .layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "1");
+    assert_eq!(res.into_body(), "1");
+    assert_eq!(gen.count(), 1);
+
+    let gen = CounterGenerator::default();
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()))
+                                   .layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()).force_overwrite())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    //Outer layer writes its own id last
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "1");
+    assert_eq!(res.into_body(), "2");
+    assert_eq!(gen.count(), 2);
+}
+
+#[tokio::test]
+async fn should_reuse_only_own_extension_by_default() {
+    use tower::util::MapRequestLayer;
+    use tower_http_req_id::RequestId;
+
+    fn insert_tenant(mut req: Request<Body>) -> Request<Body> {
+        //Other middleware, which uses String for own purposes
+        req.extensions_mut().insert("tenant".to_owned());
+        req
+    }
+
+    let gen = CounterGenerator::default();
+    let svc = ServiceBuilder::new().layer(MapRequestLayer::new(insert_tenant))
+                                   .layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()))
+                                   .layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       assert_eq!(req.extensions().get::<String>().expect("tenant is not inserted"), "tenant");
+                                       let id = req.extensions().get::<RequestId<String>>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.0.clone()))
+                                   }));
+
+    //Unrelated extension is not echoed, while ID of outer layer is re-used by nested one
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "1");
+    assert_eq!(res.into_body(), "1");
+    assert_eq!(gen.count(), 1);
+
+    let svc = ServiceBuilder::new().layer(MapRequestLayer::new(insert_tenant))
+                                   .layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()).reuse_any_extension())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "tenant");
+    assert_eq!(res.into_body(), "tenant");
+    assert_eq!(gen.count(), 1);
+}
+
+#[tokio::test]
+async fn should_not_overwrite_conflicting_extension() {
+    use tower::util::MapRequestLayer;
+    use tower_http_req_id::RequestId;
+
+    let svc = ServiceBuilder::new().layer(MapRequestLayer::new(|mut req: Request<Body>| {
+                                       //Other middleware, which uses String for own purposes
+                                       req.extensions_mut().insert("tenant".to_owned());
+                                       req
+                                   }))
+                                   .layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).overwrite_extension())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let tenant = req.extensions().get::<String>().expect("tenant is not inserted");
+                                       let id = req.extensions().get::<RequestId<String>>().expect("required-id is not inserted");
+                                       assert_eq!(format!("{:?}", id), "RequestId(***)");
+                                       Ok::<_, Infallible>(Response::new(format!("{}/{}", tenant, id)))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.into_body(), format!("tenant/{}", TEST_ID));
+
+    let svc = ServiceBuilder::new().layer(MapRequestLayer::new(|mut req: Request<Body>| {
+                                       req.extensions_mut().insert("tenant".to_owned());
+                                       req
+                                   }))
+                                   .layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).force_overwrite())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       assert!(req.extensions().get::<RequestId<String>>().is_none());
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_write_structured_header() {
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator).structured_header(http::header::FORWARDED, "by=svcA;id={id}");
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.headers().get(http::header::FORWARDED).expect("to have forwarded header").to_str().unwrap(), "by=svcA;id=id");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client");
+    assert_eq!(res.headers().get(http::header::FORWARDED).expect("to have forwarded header").to_str().unwrap(), "by=svcA;id=client");
+}
+
+#[tokio::test]
+async fn should_use_borrowed_and_shared_generator() {
+    static GEN: TestGenerator = TestGenerator;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(&GEN))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+
+    let gen = std::sync::Arc::new(CounterGenerator::default());
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "1");
+    assert_eq!(gen.count(), 1);
+}
+
+#[tokio::test]
+async fn should_generate_and_resolve_id_outside_of_middleware() {
+    use tower_http_req_id::{generate, resolve_from_headers};
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(generate::<_, String>(&TestGenerator), res.into_body());
+
+    let mut headers = http::HeaderMap::new();
+    assert_eq!(resolve_from_headers::<_, String>(&headers, &TestGenerator), TEST_ID);
+    headers.insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    assert_eq!(resolve_from_headers::<_, String>(&headers, &TestGenerator), "client");
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn should_generate_uuid_outside_of_middleware() {
+    use tower_http_req_id::{generate, resolve_from_headers, Uuid, UuidGenerator};
+
+    let gen = UuidGenerator::new();
+    let id: Uuid = generate(&gen);
+    let mut headers = http::HeaderMap::new();
+    headers.insert(HEADER_NAME, http::HeaderValue::from_str(&id.to_str()).unwrap());
+    assert_eq!(resolve_from_headers::<_, Uuid>(&headers, &gen), id);
+
+    headers.insert(HEADER_NAME, http::HeaderValue::from_static("not-uuid"));
+    assert_ne!(resolve_from_headers::<_, Uuid>(&headers, &gen), id);
+}
+
+#[tokio::test]
+async fn should_write_response_header_on_success_only() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).response_header_on_success_only())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       let mut res = Response::new(id.to_owned());
+                                       if req.uri().path() == "/missing" {
+                                           *res.status_mut() = http::StatusCode::NOT_FOUND;
+                                       }
+                                       Ok::<_, Infallible>(res)
+                                   }));
+
+    let res = svc.clone().oneshot(Request::get("/missing").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::NOT_FOUND);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), TEST_ID);
+
+    let res = svc.oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+}
+
+#[test]
+fn should_return_typed_errors() {
+    use tower_http_req_id::{to_header_value, RequestIdError};
+
+    let error = GenerateRequestIdLayer::<_, String>::new(TestGenerator).try_structured_header("invalid name", "id={id}").err();
+    assert!(matches!(error, Some(RequestIdError::InvalidHeaderName)));
+    assert!(GenerateRequestIdLayer::<_, String>::new(TestGenerator).try_structured_header("forwarded", "id={id}").is_ok());
+
+    let error = to_header_value(&"trailing ").unwrap_err();
+    assert!(matches!(error, RequestIdError::InvalidHeaderValue));
+    assert_eq!(to_header_value(&42).unwrap(), "42");
+
+    let error: &dyn std::error::Error = &error;
+    assert!(!error.to_string().is_empty());
+}
+
+#[cfg(feature = "b3")]
+#[tokio::test]
+async fn should_handle_b3_header() {
+    use tower_http_req_id::{B3, Sampling, B3_HEADER_NAME};
+
+    const VALUE: &str = "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1-05e3ac9a4f6e3b90";
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::b3())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<B3>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(Some(*id)))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(B3_HEADER_NAME, http::HeaderValue::from_static(VALUE));
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(B3_HEADER_NAME).expect("to have b3 header").to_str().unwrap(), VALUE);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    let id = res.into_body().expect("to have id in body");
+    assert_eq!(id.trace_id(), 0x80f198ee56343ba864fe8b2a57d3eff7);
+    assert_eq!(id.span_id(), 0xe457b5a2e4d86bd1);
+    assert_eq!(id.sampling(), Some(Sampling::Accept));
+    assert_eq!(id.parent_span_id(), Some(0x05e3ac9a4f6e3b90));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(B3_HEADER_NAME, http::HeaderValue::from_static("80f198ee56343ba8-E457B5A2E4D86BD1"));
+    let res = svc.oneshot(req).await.unwrap();
+    let header = res.headers().get(B3_HEADER_NAME).expect("to have b3 header").to_str().unwrap().to_owned();
+    let id = res.into_body().expect("to have id in body");
+    assert_ne!(id.span_id(), 0xe457b5a2e4d86bd1);
+    assert_eq!(header, id.to_string());
+    assert_eq!(header.parse::<B3>().unwrap(), id);
+    assert_eq!(header.len(), 49);
+
+    assert_eq!("80f198ee56343ba8-e457b5a2e4d86bd1-d".parse::<B3>().unwrap().to_string(), "80f198ee56343ba8-e457b5a2e4d86bd1-d");
+    assert!("80f198ee56343ba8".parse::<B3>().is_err());
+    assert!("80f198ee56343ba8-e457b5a2e4d86bd1-x".parse::<B3>().is_err());
+    assert!("1".parse::<B3>().is_err());
+}
+
+#[cfg(feature = "amzn")]
+#[tokio::test]
+async fn should_handle_amzn_trace_id_header() {
+    use core::time::Duration;
+    use tower_http_req_id::{AmznTraceId, AmznTraceIdGenerator, Clock, AMZN_TRACE_ID_HEADER_NAME};
+
+    const ROOT: &str = "1-5759e988-bd862e3fe1be46a994272793";
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::amzn_trace_id())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<AmznTraceId>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(Some(*id)))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(AMZN_TRACE_ID_HEADER_NAME, http::HeaderValue::from_static("Self=1-67891234-12456789abcdef012345678;Root=1-5759e988-bd862e3fe1be46a994272793;Sampled=1"));
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(AMZN_TRACE_ID_HEADER_NAME).expect("to have trace header"), "Root=1-5759e988-bd862e3fe1be46a994272793");
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    let id = res.into_body().expect("to have id in body");
+    assert_eq!(id.root(), ROOT);
+    assert_eq!(id.time(), 0x5759e988);
+    assert_eq!(id.id(), 0xbd862e3fe1be46a994272793);
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(AMZN_TRACE_ID_HEADER_NAME, http::HeaderValue::from_static("Root=2-5759e988-bd862e3fe1be46a994272793"));
+    let res = svc.oneshot(req).await.unwrap();
+    let header = res.headers().get(AMZN_TRACE_ID_HEADER_NAME).expect("to have trace header").to_str().unwrap().to_owned();
+    let id = res.into_body().expect("to have id in body");
+    assert_eq!(header, id.to_string());
+    assert_eq!(header.parse::<AmznTraceId>().unwrap(), id);
+    assert_eq!(header.len(), 40);
+    let root = header.strip_prefix("Root=1-").expect("to have root of version 1");
+    assert!(root.bytes().all(|byte| byte == b'-' || byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte)));
+    assert_eq!(root.find('-'), Some(8));
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as u32;
+    assert!(now - id.time() < 60);
+
+    struct FixedClock;
+
+    impl Clock for FixedClock {
+        fn unix_time(&self) -> Duration {
+            Duration::from_secs(0x5759e988)
+        }
+    }
+
+    let id: AmznTraceId = IdGen::gen(&AmznTraceIdGenerator::with_clock(FixedClock));
+    assert!(id.root().starts_with("1-5759e988-"));
+    assert!(id.id() < 1 << 96);
+
+    assert_eq!(" Root=1-5759e988-bd862e3fe1be46a994272793 ; Parent=53995c3f42cd8ad8".parse::<AmznTraceId>().unwrap().root(), ROOT);
+    assert!("1-5759e988-bd862e3fe1be46a994272793".parse::<AmznTraceId>().is_err());
+    assert!("Root=1-5759e988-BD862E3FE1BE46A994272793".parse::<AmznTraceId>().is_err());
+    assert!("Root=1-5759e988-bd862e3fe1be46a99427279".parse::<AmznTraceId>().is_err());
+    assert!("Root=1-5759e988-bd862e3fe1be46a994272793-1".parse::<AmznTraceId>().is_err());
+    assert!("Parent=53995c3f42cd8ad8;Sampled=1".parse::<AmznTraceId>().is_err());
+}
+
+#[tokio::test]
+async fn should_derive_id_from_seed_in_extensions() {
+    use tower_http_req_id::SeededGenerator;
+
+    #[derive(Clone)]
+    struct Seed {
+        tenant: &'static str,
+        shard: u32,
+    }
+
+    fn derive(seed: &Seed) -> String {
+        format!("{}-{}", seed.tenant, seed.shard)
+    }
+
+    let gen = CounterGenerator::default();
+    let svc = ServiceBuilder::new().map_request(|mut req: Request<Body>| {
+                                       if req.uri().path() == "/seeded" {
+                                           req.extensions_mut().insert(Seed { tenant: "tenant", shard: 1 });
+                                       }
+                                       req
+                                   })
+                                   .layer(GenerateRequestIdLayer::<_, String>::new(SeededGenerator::new(gen.clone(), derive)))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    for _ in 0..2 {
+        let res = svc.clone().oneshot(Request::get("/seeded").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(res.into_body(), "tenant-1");
+    }
+    assert_eq!(gen.count(), 0);
+
+    let res = svc.clone().oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.into_body(), "1");
+    let res = svc.oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.into_body(), "2");
+}
+
+#[tokio::test]
+async fn should_convert_generator_into_layer() {
+    let layer: GenerateRequestIdLayer<_, String> = TestGenerator.into();
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_propagate_id_to_outgoing_request() {
+    use tower_http_req_id::PropagateRequestIdLayer;
+
+    let client = ServiceBuilder::new().layer(PropagateRequestIdLayer::<String>::new())
+                                      .service(service_fn(|req: Request<Body>| async move {
+                                          let id = req.headers().get(HEADER_NAME).map(|id| id.to_str().unwrap().to_owned()).unwrap_or_default();
+                                          Ok::<_, Infallible>(Response::new(id))
+                                      }));
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(move |req: Request<Body>| {
+                                       let client = client.clone();
+                                       async move {
+                                           let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                           let mut outgoing = Request::new(Body::empty());
+                                           outgoing.extensions_mut().insert(id.clone());
+                                           client.oneshot(outgoing).await
+                                       }
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.into_body(), TEST_ID);
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "client");
+}
+
+#[cfg(feature = "random")]
+#[tokio::test]
+async fn should_generate_base62_id() {
+    use tower_http_req_id::{Base62, Base62Generator};
+
+    let gen = Base62Generator::new(16);
+    for _ in 0..100 {
+        let id: String = gen.gen();
+        assert_eq!(id.len(), gen.token_len());
+        assert!(id.bytes().all(|byte| byte.is_ascii_alphanumeric()));
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, Base62>::new(gen))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<Base62>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_string()))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("abcXYZ019"));
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "abcXYZ019");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("abc-XYZ"));
+    let res = svc.oneshot(req).await.unwrap();
+    let id = res.into_body();
+    assert_ne!(id, "abc-XYZ");
+    assert_eq!(id.len(), gen.token_len());
+}
+
+#[test]
+fn should_set_id_without_tower() {
+    use tower_http_req_id::{set_request_id, set_response_id, RequestIdError};
+
+    let mut req = Request::new(());
+    let id: String = set_request_id(&mut req, &TestGenerator);
+    assert_eq!(id, TEST_ID);
+    assert_eq!(req.extensions().get::<String>().unwrap(), TEST_ID);
+    //Already set
+    assert_eq!(set_request_id::<_, _, String>(&mut req, &ValueGenerator("other")), TEST_ID);
+
+    let mut req = Request::new(());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let id: String = set_request_id(&mut req, &TestGenerator);
+    assert_eq!(id, "client");
+    assert_eq!(req.extensions().get::<String>().unwrap(), "client");
+
+    let mut res = Response::new(());
+    set_response_id(&mut res, &id).unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client");
+
+    let mut res = Response::new(());
+    assert_eq!(set_response_id(&mut res, &"invalid\n"), Err(RequestIdError::InvalidHeaderValue));
+    assert!(res.headers().get(HEADER_NAME).is_none());
+}
+
+#[tokio::test]
+async fn should_regenerate_empty_id() {
+    let handler = service_fn(|req: Request<Body>| async move {
+        let id = req.extensions().get::<String>().expect("required-id is not inserted");
+        Ok::<_, Infallible>(Response::new(id.to_owned()))
+    });
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(handler);
+    for value in ["", "  ", " \t "] {
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(value));
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+        assert_eq!(res.into_body(), TEST_ID);
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).accept_empty_ids())
+                                   .service(handler);
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(""));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "");
+    assert_eq!(res.into_body(), "");
+}
+
+#[tokio::test]
+async fn should_use_cached_header_value_of_const_id() {
+    use tower_http_req_id::{to_header_value, ConstGenerator};
+
+    let gen = ConstGenerator::new(42u64);
+    assert_eq!(gen.header_value(&42), Some(to_header_value(&42).unwrap()));
+    assert_eq!(gen.header_value(&7), None);
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, u64>::new(gen))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<u64>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(*id))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "42");
+    assert_eq!(res.into_body(), 42);
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("7"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "7");
+}
+
+#[tokio::test]
+async fn should_map_resolved_id() {
+    fn prefix(id: String) -> String {
+        format!("svc-{}", id)
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).map_id(prefix))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "svc-id");
+    assert_eq!(res.into_body(), "svc-id");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "svc-client");
+    assert_eq!(res.into_body(), "svc-client");
+}
+
+#[tokio::test]
+async fn should_derive_response_value_from_id() {
+    let timestamp = GenerateRequestIdLayer::<_, String>::new(TestGenerator).response_value(|id| id.split('-').next().unwrap_or_default().to_owned());
+    let svc = ServiceBuilder::new().layer(timestamp)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("1700000000-node-42"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "1700000000");
+    assert_eq!(res.into_body(), "1700000000-node-42");
+}
+
+#[tokio::test]
+async fn should_replace_header_in_populated_response() {
+    const HEADERS: usize = 1000;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       let mut res = Response::new(Body::empty());
+                                       let headers = res.headers_mut();
+                                       for idx in 0..HEADERS {
+                                           let name = http::HeaderName::from_bytes(format!("x-custom-{}", idx).as_bytes()).unwrap();
+                                           headers.insert(name, http::HeaderValue::from(idx));
+                                       }
+                                       headers.append(HEADER_NAME, http::HeaderValue::from_static("first"));
+                                       headers.append(HEADER_NAME, http::HeaderValue::from_static("second"));
+                                       Ok::<_, Infallible>(res)
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    let ids = res.headers().get_all(HEADER_NAME).iter().collect::<Vec<_>>();
+    assert_eq!(ids, [TEST_ID]);
+    assert_eq!(res.headers().len(), HEADERS + 1);
+    assert_eq!(res.headers().get("x-custom-999").expect("to keep custom header"), "999");
+}
+
+#[tokio::test]
+async fn should_read_id_from_query_param() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).query_param("rid"))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let req = Request::builder().uri("/hook?event=push&rid=webhook-1").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "webhook-1");
+    assert_eq!(res.into_body(), "webhook-1");
+
+    let req = Request::builder().uri("/hook?rid=webhook-1").header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "client");
+
+    let req = Request::builder().uri("/hook?rid=&ridx=other").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[cfg(feature = "zeroize")]
+#[tokio::test]
+async fn should_handle_secret_id() {
+    use tower_http_req_id::SecretId;
+
+    #[derive(Clone)]
+    struct SecretGenerator;
+
+    impl IdGen<SecretId> for SecretGenerator {
+        fn gen(&self) -> SecretId {
+            SecretId::new("secret".to_owned())
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, SecretId>::new(SecretGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<SecretId>().expect("required-id is not inserted");
+                                       assert_eq!(format!("{:?}", id), "SecretId(..)");
+                                       Ok::<_, Infallible>(Response::new(id.as_str().to_owned()))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "secret");
+    assert_eq!(res.into_body(), "secret");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client-secret"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client-secret");
+    assert_eq!(res.into_body(), "client-secret");
+}
+
+#[tokio::test]
+async fn should_skip_request_extension() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).skip_request_extension())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       assert!(req.extensions().get::<String>().is_none());
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client");
+}
+
+#[tokio::test]
+async fn should_use_type_erased_generator() {
+    use std::sync::Arc;
+    use tower_http_req_id::{generate, StaticGenerator, StaticId};
+
+    fn select(name: &str) -> Box<dyn IdGen<String> + Send + Sync> {
+        match name {
+            "test" => Box::new(TestGenerator),
+            _ => Box::new(ValueGenerator("value")),
+        }
+    }
+
+    assert_eq!(generate(&select("test")), TEST_ID);
+    assert_eq!(generate(&select("value")), "value");
+
+    let gen: Arc<dyn IdGen<String> + Send + Sync> = select("value").into();
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "value");
+
+    let gen: Arc<dyn IdGen<StaticId> + Send + Sync> = Arc::new(StaticGenerator::new("static"));
+    assert_eq!(gen.header_value(&StaticId("static")), Some(http::HeaderValue::from_static("static")));
+}
+
+#[tokio::test]
+async fn should_inherit_id_of_parent_request() {
+    use tower_http_req_id::inherit_request_id;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|parent: Request<Body>| async move {
+                                       let mut children = [Request::new(()), Request::new(())];
+                                       for child in children.iter_mut() {
+                                           assert_eq!(inherit_request_id::<String, _, _>(&parent, child), Ok(true));
+                                           assert_eq!(child.headers().get(HEADER_NAME).expect("to have request-id header"), "client");
+                                           assert_eq!(child.extensions().get::<String>().expect("to have id"), "client");
+                                       }
+
+                                       let mut child = Request::new(());
+                                       assert_eq!(inherit_request_id::<u64, _, _>(&parent, &mut child), Ok(false));
+                                       assert!(child.headers().get(HEADER_NAME).is_none());
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    svc.oneshot(req).await.unwrap();
+}
+
+#[cfg(all(feature = "random", feature = "std"))]
+#[test]
+fn should_generate_time_sortable_ids() {
+    use tower_http_req_id::TimeSortableGenerator;
+
+    let gen = TimeSortableGenerator::new();
+    let earlier = gen.gen();
+    std::thread::sleep(core::time::Duration::from_millis(2));
+    let later = gen.gen();
+    assert_eq!(earlier.len(), gen.id_len());
+    assert!(later > earlier, "{} <= {}", later, earlier);
+}
+
+#[tokio::test]
+async fn should_match_mixed_case_header_name() {
+    let name = http::HeaderName::from_bytes(b"X-Trace-ID").unwrap();
+    assert_eq!(name, "x-trace-id");
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).header_name(name))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    for incoming in ["x-trace-id", "X-TRACE-ID", "x-Trace-Id"] {
+        let req = Request::builder().header(incoming, "Client-ID").body(Body::empty()).unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get("X-Trace-Id").expect("to have trace-id header").to_str().unwrap(), "Client-ID");
+        assert!(res.headers().keys().all(|name| name.as_str() == "x-trace-id"));
+        assert_eq!(res.into_body(), "Client-ID");
+    }
+}
+
+#[tokio::test]
+async fn should_resolve_immediate_response_future() {
+    use tower_http_req_id::ResponseFut;
+
+    type InnerFut = core::future::Ready<Result<Response<&'static str>, Infallible>>;
+
+    let mut res = Response::new("immediate");
+    *res.status_mut() = http::StatusCode::BAD_REQUEST;
+    let res = ResponseFut::<InnerFut, String, _>::immediate(res).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), "immediate");
+}
+
+#[cfg(feature = "scope")]
+#[tokio::test]
+async fn should_scope_id_to_inner_future() {
+    use tower_http_req_id::current_request_id;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).scope_id())
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       assert_eq!(current_request_id::<u64>(), None);
+                                       tokio::task::yield_now().await;
+                                       let id = current_request_id::<String>().expect("to have id in scope");
+                                       Ok::<_, Infallible>(Response::new(id))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "client");
+    assert_eq!(current_request_id::<String>(), None);
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(current_request_id::<String>()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.into_body(), None);
+}
+
+#[tokio::test]
+async fn should_trim_incoming_id() {
+    let handler = service_fn(|req: Request<Body>| async move {
+        let id = req.extensions().get::<String>().expect("required-id is not inserted");
+        Ok::<_, Infallible>(Response::new(id.to_owned()))
+    });
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(handler);
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(" abc\t "));
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "abc");
+    assert_eq!(res.into_body(), "abc");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("   "));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), TEST_ID);
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).preserve_whitespace())
+                                   .service(handler);
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(" abc "));
+    let res = svc.oneshot(req).await.unwrap();
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), " abc ");
+}
+
+#[tokio::test]
+async fn should_write_always_valid_id() {
+    #[derive(Clone)]
+    struct IntGenerator;
+
+    impl IdGen<u64> for IntGenerator {
+        #[inline(always)]
+        fn gen(&self) -> u64 {
+            1
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, u64>::new(IntGenerator).zero_pad(4).assume_valid_header())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<u64>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(*id))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "0001");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("123456"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "123456");
+}
+
+#[tokio::test]
+async fn should_zero_pad_negative_id_the_same_way_regardless_of_validation() {
+    #[derive(Clone)]
+    struct NegativeGenerator;
+
+    impl IdGen<i32> for NegativeGenerator {
+        #[inline(always)]
+        fn gen(&self) -> i32 {
+            -42
+        }
+    }
+
+    let layer = GenerateRequestIdLayer::<_, i32>::new(NegativeGenerator).zero_pad(5);
+    for layer in [layer.clone(), layer.assume_valid_header()] {
+        let svc = ServiceBuilder::new().layer(layer)
+                                       .service(service_fn(|_: Request<Body>| async move {
+                                           Ok::<_, Infallible>(Response::new(Body::empty()))
+                                       }));
+
+        let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+        let header = res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap();
+        assert_eq!(header, "-0042");
+        assert_eq!(header.parse::<i32>().unwrap(), -42);
+    }
+}
+
+#[cfg(feature = "uuid")]
+#[tokio::test]
+async fn should_write_always_valid_uuid() {
+    use tower_http_req_id::Uuid;
+
+    #[derive(Clone)]
+    struct UuidFormatGenerator;
+
+    //Doesn't provide pre-built header value, so formatting is performed.
+    impl IdGen<Uuid> for UuidFormatGenerator {
+        fn gen(&self) -> Uuid {
+            tower_http_req_id::UuidGenerator::new_v4().gen()
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, Uuid>::new(UuidFormatGenerator).assume_valid_header())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = *req.extensions().get::<Uuid>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    let header = res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap().to_owned();
+    assert_eq!(header, res.into_body().to_string());
+}
+
+#[tokio::test]
+async fn should_parse_id_with_custom_parser() {
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator).parse_with(|text| text.strip_prefix("req_").map(str::to_owned));
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("req_client"));
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "client");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_skip_not_modified_response() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).skip_not_modified())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let mut res = Response::new(Body::empty());
+                                       match req.uri().path() {
+                                           "/cached" => *res.status_mut() = http::StatusCode::NOT_MODIFIED,
+                                           "/missing" => *res.status_mut() = http::StatusCode::NOT_FOUND,
+                                           _ => (),
+                                       }
+                                       Ok::<_, Infallible>(res)
+                                   }));
+
+    let res = svc.clone().oneshot(Request::get("/cached").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::NOT_MODIFIED);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+
+    for path in ["/", "/missing"] {
+        let res = svc.clone().oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn should_tag_id_with_thread() {
+    use tower_http_req_id::ThreadTaggedGenerator;
+
+    let gen = ThreadTaggedGenerator::new(CounterGenerator::default());
+    let main = gen.gen();
+    let (tag, seq) = main.split_once('-').expect("to have thread tag");
+    assert!(!tag.is_empty() && tag.bytes().all(|byte| byte.is_ascii_digit()));
+    assert_eq!(seq, "1");
+    assert_eq!(gen.gen(), format!("{}-2", tag));
+
+    let other = std::thread::scope(|scope| scope.spawn(|| gen.gen()).join().unwrap());
+    let (other_tag, seq) = other.split_once('-').expect("to have thread tag");
+    assert_ne!(other_tag, tag);
+    assert_eq!(seq, "3");
+}
+
+#[cfg(feature = "uuid")]
+#[tokio::test]
+async fn should_require_uuid_version() {
+    use tower_http_req_id::{Uuid, UuidGenerator, UuidVersion};
+
+    const V1: &str = "6fa459ea-ee8a-11e3-ac10-0800200c9a66";
+    const V4: &str = "16fd2706-8baf-433b-82eb-8c7fada847da";
+
+    let layer = GenerateRequestIdLayer::<_, Uuid>::new(UuidGenerator::new_v4()).require_uuid_version(UuidVersion::Random);
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = *req.extensions().get::<Uuid>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(V4));
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.into_body().to_string(), V4);
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(V1));
+    let res = svc.oneshot(req).await.unwrap();
+    let id = res.into_body();
+    assert_ne!(id.to_string(), V1);
+    assert!(id.is_version(UuidVersion::Random));
+}
+
+#[cfg(feature = "trailer")]
+#[tokio::test]
+async fn should_write_id_into_trailers() {
+    use hyper::body::HttpBody;
+    use tower_http_req_id::RequestIdTrailerLayer;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).response_header_when(|_| false))
+                                   .layer(RequestIdTrailerLayer::<String>::new())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let body = match req.uri().path() {
+                                           "/trailers" => {
+                                               let (mut sender, body) = Body::channel();
+                                               tokio::spawn(async move {
+                                                   sender.send_data("data".into()).await.unwrap();
+                                                   let mut trailers = http::HeaderMap::new();
+                                                   trailers.insert("x-checksum", http::HeaderValue::from_static("42"));
+                                                   sender.send_trailers(trailers).await.unwrap();
+                                               });
+                                               body
+                                           },
+                                           _ => Body::from("data"),
+                                       };
+                                       Ok::<_, Infallible>(Response::new(body))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    let mut body = res.into_body();
+    assert!(!body.is_end_stream());
+    assert_eq!(body.data().await.unwrap().unwrap(), "data");
+    let trailers = body.trailers().await.unwrap().expect("to have trailers");
+    assert_eq!(trailers.get(HEADER_NAME).expect("to have request-id trailer"), TEST_ID);
+    assert!(body.is_end_stream());
+
+    let res = svc.oneshot(Request::get("/trailers").body(Body::empty()).unwrap()).await.unwrap();
+    let mut body = res.into_body();
+    assert_eq!(body.data().await.unwrap().unwrap(), "data");
+    let trailers = body.trailers().await.unwrap().expect("to have trailers");
+    assert_eq!(trailers.get(HEADER_NAME).expect("to have request-id trailer"), TEST_ID);
+    assert_eq!(trailers.get("x-checksum").expect("to keep inner trailers"), "42");
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn should_write_id_into_json_body() {
+    use tower_http_req_id::{inject_request_id, RequestIdJsonLayer};
+
+    let body = inject_request_id(&serde_json::json!({"status": "ok"}), TEST_ID).unwrap();
+    assert_eq!(body, serde_json::json!({"status": "ok", "request_id": TEST_ID}));
+    let body = inject_request_id(&[1, 2], TEST_ID).unwrap();
+    assert_eq!(body, serde_json::json!([1, 2]));
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .layer(RequestIdJsonLayer::<String>::new())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let body = r#"{"status":"ok"}"#;
+                                       let content_type = match req.uri().path() {
+                                           "/json" => "application/json; charset=utf-8",
+                                           _ => "text/plain",
+                                       };
+                                       let resp = Response::builder().header(http::header::CONTENT_TYPE, content_type)
+                                                                     .header(http::header::CONTENT_LENGTH, body.len())
+                                                                     .body(Body::from(body))
+                                                                     .unwrap();
+                                       Ok::<_, Infallible>(resp)
+                                   }));
+
+    let res = svc.clone().oneshot(Request::get("/json").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+    assert!(res.headers().get(http::header::CONTENT_LENGTH).is_none());
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, serde_json::json!({"status": "ok", "request_id": TEST_ID}));
+
+    let res = svc.oneshot(Request::get("/text").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+    assert!(res.headers().get(http::header::CONTENT_LENGTH).is_some());
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(body, r#"{"status":"ok"}"#);
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn should_preserve_json_body_content_when_writing_id() {
+    use tower_http_req_id::RequestIdJsonLayer;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .layer(RequestIdJsonLayer::<String>::new())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let body = match req.uri().path() {
+                                           "/ordered" => r#" {"zeta": 1, "alpha": 123456789012345678901234567890, "nested": {"b": 1.10, "a": 2}}"#,
+                                           "/empty" => "{}",
+                                           "/existing" => r#"{"request_id":"own"}"#,
+                                           _ => r#"{"broken":"#,
+                                       };
+                                       let resp = Response::builder().header(http::header::CONTENT_TYPE, "application/json")
+                                                                     .body(Body::from(body))
+                                                                     .unwrap();
+                                       Ok::<_, Infallible>(resp)
+                                   }));
+
+    let expected = [
+        ("/ordered", r#" {"request_id":"id","zeta": 1, "alpha": 123456789012345678901234567890, "nested": {"b": 1.10, "a": 2}}"#),
+        ("/empty", r#"{"request_id":"id"}"#),
+        ("/existing", r#"{"request_id":"own"}"#),
+        ("/broken", r#"{"broken":"#),
+    ];
+    for (path, expected) in expected {
+        let res = svc.clone().oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, expected, "path={}", path);
+    }
+}
+
+#[tokio::test]
+async fn should_replace_generator_keeping_options() {
+    let base = GenerateRequestIdLayer::<_, String>::new(TestGenerator).header_name(http::HeaderName::from_static("x-trace-id"))
+                                                                      .map_id(|id| format!("svc-{}", id));
+    let svc = ServiceBuilder::new().layer(base.with_generator(ValueGenerator("value")))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.headers().get("x-trace-id").expect("to have trace-id header").to_str().unwrap(), "svc-value");
+    assert_eq!(res.into_body(), "svc-value");
+
+    let req = Request::builder().header("x-trace-id", "client").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "svc-client");
+}
+
+#[tokio::test]
+async fn should_use_custom_id_scheme() {
+    use tower_http_req_id::{IdScheme, CounterScheme, StringScheme};
+
+    assert!(<StringScheme<TestGenerator> as IdScheme>::validate(&"client".to_owned()));
+    assert!(!<StringScheme<TestGenerator> as IdScheme>::validate(&" client".to_owned()));
+
+    //Order ID in format `ord-{number}`, accepting only numbers below 1000 from client.
+    #[derive(Clone)]
+    struct OrderScheme;
+
+    impl IdGen<String> for OrderScheme {
+        fn gen(&self) -> String {
+            "ord-1".to_owned()
+        }
+
+        fn header_value(&self, id: &String) -> Option<http::HeaderValue> {
+            http::HeaderValue::from_str(&id.to_uppercase()).ok()
+        }
+    }
+
+    impl IdScheme for OrderScheme {
+        type Id = String;
+
+        fn parse(text: &str) -> Option<String> {
+            text.strip_prefix("ord-").map(|_| text.to_owned())
+        }
+
+        fn validate(id: &String) -> bool {
+            id[4..].parse::<u16>().map(|num| num < 1000).unwrap_or(false)
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::with_scheme(OrderScheme))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    for (incoming, expected) in [("ord-42", "ord-42"), ("ord-1000", "ord-1"), ("42", "ord-1"), ("ord-x", "ord-1")] {
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(incoming));
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), expected.to_uppercase());
+        assert_eq!(res.into_body(), expected);
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::with_scheme(CounterScheme::new()))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<u64>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(*id))
+                                   }));
+    for (incoming, expected) in [("7", 7), ("0", 1), ("+8", 2)] {
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(incoming));
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), expected.to_string());
+        assert_eq!(res.into_body(), expected);
+    }
+}
+
+#[cfg(feature = "hash")]
+#[tokio::test]
+async fn should_write_hashed_id() {
+    const HASH: &str = "42bd3da71c17bc5eba4e964dfe161ce586c7df1b49623f12d049c673a5d160e3";
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).response_hash(b"salt"))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    for _ in 0..2 {
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), HASH);
+        assert_eq!(res.into_body(), "client");
+    }
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("other"));
+    let res = svc.oneshot(req).await.unwrap();
+    let header = res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap();
+    assert_ne!(header, HASH);
+    assert_ne!(header, "other");
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn should_verify_hashed_id() {
+    use tower_http_req_id::verify_response_hash;
+
+    const HASH: &str = "42bd3da71c17bc5eba4e964dfe161ce586c7df1b49623f12d049c673a5d160e3";
+
+    assert!(verify_response_hash(b"salt", &"client", HASH.as_bytes()));
+    assert!(!verify_response_hash(b"other", &"client", HASH.as_bytes()));
+    assert!(!verify_response_hash(b"salt", &"other", HASH.as_bytes()));
+    assert!(!verify_response_hash(b"salt", &"client", HASH.to_ascii_uppercase().as_bytes()));
+    assert!(!verify_response_hash(b"salt", &"client", &HASH.as_bytes()[..63]));
+    assert!(!verify_response_hash(b"salt", &"client", b""));
+
+    let mut tampered = HASH.as_bytes().to_vec();
+    tampered[63] = b'0';
+    assert!(!verify_response_hash(b"salt", &"client", &tampered));
+}
+
+#[tokio::test]
+async fn should_write_response_header_on_errors_only() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).response_header_on_errors_only())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       let mut res = Response::new(id.to_owned());
+                                       match req.uri().path() {
+                                           "/fail" => *res.status_mut() = http::StatusCode::INTERNAL_SERVER_ERROR,
+                                           "/missing" => *res.status_mut() = http::StatusCode::NOT_FOUND,
+                                           _ => (),
+                                       }
+                                       Ok::<_, Infallible>(res)
+                                   }));
+
+    for path in ["/fail", "/missing"] {
+        let res = svc.clone().oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+        assert_eq!(res.into_body(), TEST_ID);
+    }
+
+    let res = svc.oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_use_generator_write_id() {
+    use core::fmt;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Clone)]
+    struct PartsGenerator;
+
+    #[derive(Clone)]
+    struct PartsId(&'static str, u32);
+
+    impl fmt::Display for PartsId {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(fmt, "{}-{}", self.0, self.1)
+        }
+    }
+
+    impl core::str::FromStr for PartsId {
+        type Err = ();
+
+        fn from_str(_: &str) -> Result<Self, Self::Err> {
+            Err(())
+        }
+    }
+
+    fn write_parts(id: &PartsId, out: &mut dyn fmt::Write) -> fmt::Result {
+        WRITES.fetch_add(1, Ordering::SeqCst);
+        out.write_str(id.0)?;
+        out.write_char('-')?;
+        write!(out, "{}", id.1)
+    }
+
+    impl IdGen<PartsId> for PartsGenerator {
+        fn gen(&self) -> PartsId {
+            PartsId("node", 42)
+        }
+
+        fn write_id(&self) -> Option<fn(&PartsId, &mut dyn fmt::Write) -> fmt::Result> {
+            Some(write_parts)
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, PartsId>::new(PartsGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<PartsId>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_string()))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(WRITES.load(Ordering::SeqCst), 1);
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), PartsGenerator.gen().to_string());
+    assert_eq!(res.into_body(), "node-42");
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, PartsId>::new(PartsGenerator).zero_pad(10))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(WRITES.load(Ordering::SeqCst), 2);
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "000node-42");
+}
+
+#[tokio::test]
+async fn should_read_id_from_grpc_bin_metadata() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).grpc_metadata())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    //base64 of "client-id" and "client-id2", with and without padding
+    for encoded in ["Y2xpZW50LWlk", "Y2xpZW50LWlkMg==", "Y2xpZW50LWlkMg"] {
+        let req = Request::get("/").header("x-request-id-bin", encoded).body(Body::empty()).unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        let expected = match encoded {
+            "Y2xpZW50LWlk" => "client-id",
+            _ => "client-id2",
+        };
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), expected);
+        assert_eq!(res.into_body(), expected);
+    }
+
+    let req = Request::get("/").header(HEADER_NAME, "text-id").header("x-request-id-bin", "Y2xpZW50LWlk").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "text-id");
+
+    let req = Request::get("/").header("x-request-id-bin", "not base64!").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), TEST_ID);
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).grpc_metadata().header_name(http::HeaderName::from_static("x-trace")))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let req = Request::get("/").header("x-trace-bin", "Y2xpZW50LWlk").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "client-id");
+}
+
+#[tokio::test]
+async fn should_apply_policy_fn() {
+    use tower_http_req_id::{IdPolicy, RequestRef};
+
+    fn policy(req: RequestRef<'_>) -> IdPolicy {
+        match req.uri().path() {
+            "/public" => IdPolicy::Generate,
+            "/forbidden" => IdPolicy::Reject(http::StatusCode::FORBIDDEN),
+            _ => IdPolicy::Trust,
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).policy_fn(policy))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let req = Request::get("/public").header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+
+    let req = Request::get("/internal").header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client");
+    assert_eq!(res.into_body(), "client");
+
+    let req = Request::get("/forbidden").header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::FORBIDDEN);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), "");
+}
+
+#[tokio::test]
+async fn should_validate_const_id_on_construction() {
+    use tower_http_req_id::{ConstGenerator, StaticGenerator, StaticId, RequestIdError};
+
+    const INVALID: Result<StaticGenerator, RequestIdError> = StaticGenerator::try_new("static\nid");
+    assert_eq!(INVALID.err(), Some(RequestIdError::InvalidHeaderValue));
+    assert_eq!(StaticGenerator::try_new(" static").err(), Some(RequestIdError::InvalidHeaderValue));
+    assert_eq!(ConstGenerator::try_new("const\0id".to_owned()).err(), Some(RequestIdError::InvalidHeaderValue));
+
+    let gen = ConstGenerator::try_new("const-id".to_owned()).expect("to be valid");
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "const-id");
+    assert_eq!(res.into_body(), "const-id");
+
+    let gen = StaticGenerator::try_new("static-id").expect("to be valid");
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, StaticId>::new(gen))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "static-id");
+}
+
+#[tokio::test]
+async fn should_resolve_two_ids_in_one_layer() {
+    use tower_http_req_id::CounterScheme;
+
+    let trace_header = http::HeaderName::from_static("x-trace-id");
+    let span_header = http::HeaderName::from_static("x-span-id");
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator).header_name(trace_header.clone())
+                                                                      .stack_id(GenerateRequestIdLayer::<_, u64>::new(CounterScheme::new()).header_name(span_header.clone()).generate_for_methods(&[http::Method::GET]));
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let trace = req.extensions().get::<String>().expect("trace id is not inserted");
+                                       let span = req.extensions().get::<u64>().expect("span id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(format!("{}/{}", trace, span)))
+                                   }));
+
+    let req = Request::get("/").header(&trace_header, "trace").header(&span_header, "100").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(&trace_header).expect("to have trace header").to_str().unwrap(), "trace");
+    let span = res.headers().get(&span_header).expect("to have span header").to_str().unwrap().to_owned();
+    assert_ne!(span, "100");
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), format!("trace/{}", span));
+
+    let req = Request::post("/").header(&span_header, "100").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(&trace_header).expect("to have trace header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.headers().get(&span_header).expect("to have span header").to_str().unwrap(), "100");
+    assert_eq!(res.into_body(), format!("{}/100", TEST_ID));
+}
+
+#[tokio::test]
+async fn should_stack_layers_of_the_same_id_type() {
+    use tower_http_req_id::RequestId;
+
+    let trace_header = http::HeaderName::from_static("x-trace-id");
+    let span_header = http::HeaderName::from_static("x-span-id");
+    let gen = CounterGenerator::default();
+    let trace = GenerateRequestIdLayer::<_, String>::new(gen.clone()).header_name(trace_header.clone());
+    let span = GenerateRequestIdLayer::<_, String>::new(gen.clone()).header_name(span_header.clone());
+
+    //Span layer re-uses ID of trace layer
+    let svc = ServiceBuilder::new().layer(trace.clone().stack_id(span.clone()))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       assert!(req.extensions().get::<RequestId<String>>().is_none());
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(&trace_header).expect("to have trace header"), "1");
+    assert_eq!(res.headers().get(&span_header).expect("to have span header"), "1");
+    assert_eq!(res.into_body(), "1");
+
+    let svc = ServiceBuilder::new().layer(trace.stack_id(span.overwrite_extension()))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let trace = req.extensions().get::<String>().expect("trace id is not inserted");
+                                       let span = req.extensions().get::<RequestId<String>>().expect("span id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(format!("{}/{}", trace, span.0)))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(&trace_header).expect("to have trace header"), "2");
+    assert_eq!(res.headers().get(&span_header).expect("to have span header"), "3");
+    assert_eq!(res.into_body(), "2/3");
+    assert_eq!(gen.count(), 3);
+}
+
+#[tokio::test]
+async fn should_generate_id_without_from_str() {
+    use core::fmt;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Tick(u64);
+
+    impl fmt::Display for Tick {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(fmt, "tick-{}", self.0)
+        }
+    }
+
+    #[derive(Clone)]
+    struct TickGenerator;
+
+    impl IdGen<Tick> for TickGenerator {
+        fn gen(&self) -> Tick {
+            Tick(1)
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, Tick>::generate_only(TickGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<Tick>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_string()))
+                                   }));
+
+    let req = Request::get("/").header(HEADER_NAME, "tick-2").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "tick-1");
+    assert_eq!(res.into_body(), "tick-1");
+
+    fn parse_tick(text: &str) -> Option<Tick> {
+        text.strip_prefix("tick-").and_then(|num| num.parse().ok()).map(Tick)
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, Tick>::generate_only(TickGenerator).parse_with(parse_tick))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<Tick>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_string()))
+                                   }));
+    let req = Request::get("/").header(HEADER_NAME, "tick-2").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "tick-2");
+}
+
+#[tokio::test]
+async fn should_generate_only_id_with_from_str() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::generate_only(TestGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let req = Request::get("/").header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let req = Request::get("/").header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "client");
+}
+
+#[tokio::test]
+async fn should_reject_request_without_required_header() {
+    #[derive(Clone)]
+    struct OneGenerator;
+
+    impl IdGen<u64> for OneGenerator {
+        fn gen(&self) -> u64 {
+            1
+        }
+    }
+
+    let make_svc = |layer: GenerateRequestIdLayer<OneGenerator, u64, tower_http_req_id::DefaultRejection>| {
+        ServiceBuilder::new().layer(layer)
+                             .service(service_fn(|req: Request<Body>| async move {
+                                 let id = req.extensions().get::<u64>().expect("required-id is not inserted");
+                                 Ok::<_, Infallible>(Response::new(id.to_string()))
+                             }))
+    };
+
+    let svc = make_svc(GenerateRequestIdLayer::new(OneGenerator).require_header(http::StatusCode::BAD_REQUEST));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.into_body(), "");
+
+    let req = Request::get("/").header(HEADER_NAME, "42").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "42");
+    assert_eq!(res.into_body(), "42");
+
+    //Invalid header is replaced unless strict validation is requested
+    let req = Request::get("/").header(HEADER_NAME, "invalid").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert_eq!(res.into_body(), "1");
+
+    let svc = make_svc(GenerateRequestIdLayer::new(OneGenerator).require_valid_header(http::StatusCode::PRECONDITION_FAILED).generate_for_methods(&[http::Method::POST]));
+
+    let req = Request::get("/").header(HEADER_NAME, "invalid").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::PRECONDITION_FAILED);
+    assert_eq!(res.into_body(), "");
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::PRECONDITION_FAILED);
+
+    let req = Request::get("/").header(HEADER_NAME, "42").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert_eq!(res.into_body(), "42");
+
+    let res = svc.oneshot(Request::post("/").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert_eq!(res.into_body(), "1");
+}
+
+#[tokio::test]
+async fn should_regenerate_string_id_with_non_ascii_header() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    for value in [&b"\xff\xfe"[..], "ид".as_bytes()] {
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_bytes(value).unwrap());
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+        assert_eq!(res.into_body(), TEST_ID);
+    }
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn should_extract_id_in_axum_handler() {
+    use tower_http_req_id::RequestId;
+
+    async fn handler(RequestId(id): RequestId<String>) -> String {
+        id
+    }
+
+    let app = axum::Router::new().route("/", axum::routing::get(handler));
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(app.clone());
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(body, TEST_ID);
+
+    let req = Request::get("/").header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(body, "client");
+
+    let res = app.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn should_clone_id_only_for_both_sinks() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountedId(Arc<AtomicUsize>);
+
+    impl Clone for CountedId {
+        fn clone(&self) -> Self {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Self(self.0.clone())
+        }
+    }
+
+    impl core::fmt::Display for CountedId {
+        fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            fmt.write_str(TEST_ID)
+        }
+    }
+
+    #[derive(Clone)]
+    struct CountedGenerator(Arc<AtomicUsize>);
+
+    impl IdGen<CountedId> for CountedGenerator {
+        #[inline(always)]
+        fn gen(&self) -> CountedId {
+            CountedId(self.0.clone())
+        }
+    }
+
+    async fn run(layer: fn(CountedGenerator) -> GenerateRequestIdLayer<CountedGenerator, CountedId>) -> (usize, bool, bool) {
+        let clones = Arc::new(AtomicUsize::new(0));
+        let svc = ServiceBuilder::new().layer(layer(CountedGenerator(clones.clone())))
+                                       .service(service_fn(|req: Request<Body>| async move {
+                                           Ok::<_, Infallible>(Response::new(req.extensions().get::<CountedId>().is_some()))
+                                       }));
+        let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+        let has_header = res.headers().get(HEADER_NAME).is_some();
+        (clones.load(Ordering::Relaxed), *res.body(), has_header)
+    }
+
+    assert_eq!(run(GenerateRequestIdLayer::generate_only).await, (1, true, true));
+    assert_eq!(run(|gen| GenerateRequestIdLayer::generate_only(gen).skip_response_header()).await, (0, true, false));
+    assert_eq!(run(|gen| GenerateRequestIdLayer::generate_only(gen).skip_request_extension()).await, (0, false, true));
+    assert_eq!(run(|gen| GenerateRequestIdLayer::generate_only(gen).skip_request_extension().skip_response_header()).await, (0, false, false));
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn should_generate_sequential_test_ids() {
+    use tower_http_req_id::test_util::SeqTestGenerator;
+
+    let gen = SeqTestGenerator::new("test");
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+
+    for expected in ["test-001", "test-002", "test-003"] {
+        let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), expected);
+    }
+
+    gen.reset();
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "test-001");
+    assert_eq!(IdGen::<String>::gen(&gen), "test-002");
+}
+
+#[tokio::test]
+async fn should_distinguish_inbound_and_generated_id() {
+    use tower_http_req_id::{HybridId, HybridGenerator};
+
+    #[derive(Clone)]
+    struct NumGenerator;
+
+    impl IdGen<u64> for NumGenerator {
+        #[inline(always)]
+        fn gen(&self) -> u64 {
+            42
+        }
+    }
+
+    type Id = HybridId<String, u64>;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, Id>::new(HybridGenerator::new(NumGenerator)))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<Id>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(Some(id.clone())))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "42");
+    let id = res.into_body().expect("to have id in body");
+    assert_eq!(id, HybridId::Generated(42));
+    assert!(id.is_generated());
+    assert_eq!(String::from(id), "42");
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client-id"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "client-id");
+    let id = res.into_body().expect("to have id in body");
+    assert!(id.is_inbound());
+    assert_eq!(String::from(id), "client-id");
+
+    assert_eq!(HybridId::<u32, u16>::Inbound(1).unify::<u64>(), 1);
+    assert_eq!(HybridId::<u32, u16>::Generated(2).unify::<u64>(), 2);
+}
+
+#[tokio::test]
+async fn should_wait_for_generator_readiness() {
+    use core::task;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::Service;
+
+    #[derive(Clone)]
+    struct BusyGenerator(Arc<AtomicUsize>);
+
+    impl IdGen<String> for BusyGenerator {
+        #[inline(always)]
+        fn gen(&self) -> String {
+            TEST_ID.to_owned()
+        }
+
+        fn poll_ready(&self, ctx: &mut task::Context<'_>) -> task::Poll<()> {
+            match self.0.fetch_sub(1, Ordering::Relaxed) {
+                0 => {
+                    self.0.store(0, Ordering::Relaxed);
+                    task::Poll::Ready(())
+                },
+                _ => {
+                    ctx.waker().wake_by_ref();
+                    task::Poll::Pending
+                },
+            }
+        }
+    }
+
+    let busy = Arc::new(AtomicUsize::new(3));
+    let mut svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(BusyGenerator(busy.clone())))
+                                       .service(service_fn(|_: Request<Body>| async move {
+                                           Ok::<_, Infallible>(Response::new(Body::empty()))
+                                       }));
+
+    let mut polls = 0;
+    core::future::poll_fn(|ctx| {
+        polls += 1;
+        svc.poll_ready(ctx)
+    }).await.unwrap();
+    assert_eq!(polls, 4);
+    assert_eq!(busy.load(Ordering::Relaxed), 0);
+
+    let res = svc.call(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_generate_fs_safe_id() {
+    use tower_http_req_id::{FsSafeGenerator, is_fs_safe};
+
+    let gen = FsSafeGenerator::new(ValueGenerator("../etc/passwd:C:\\ x"));
+    let id = IdGen::<String>::gen(&gen);
+    assert_eq!(id, "_._etc_passwd_C___x");
+    assert!(is_fs_safe(&id));
+    assert!(http::HeaderValue::from_str(&id).is_ok());
+    assert_eq!(IdGen::<String>::gen(&FsSafeGenerator::new(ValueGenerator("-rf"))), "_rf");
+    assert_eq!(IdGen::<String>::gen(&FsSafeGenerator::new(ValueGenerator(""))), "_");
+    assert_eq!(IdGen::<String>::gen(&FsSafeGenerator::new(ValueGenerator("trace-1.log"))), "trace-1.log");
+    assert_eq!(IdGen::<String>::gen(&FsSafeGenerator::new(ValueGenerator(Box::leak("a".repeat(300).into_boxed_str())))).len(), 255);
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::with_scheme(FsSafeGenerator::new(ValueGenerator("gen/id"))))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    for (client, expected) in [("client:1/..", "gen_id"), ("..", "gen_id"), ("client_1", "client_1")] {
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(client));
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), expected);
+        assert_eq!(res.into_body(), expected);
+    }
+}
+
+#[tokio::test]
+async fn should_format_header_with_value_capacity() {
+    const UUID: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(ValueGenerator(UUID)).with_value_capacity(36))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), UUID);
+}
+
+#[tokio::test]
+async fn should_tag_id_with_host() {
+    use tower_http_req_id::HostTaggedGenerator;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(HostTaggedGenerator::new(TestGenerator, "unknown")))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::get("http://Tenant.example.com:8080/path").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "tenant.example.com-id");
+
+    let req = Request::get("/path").header(http::header::HOST, "api.example.com:443").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "api.example.com-id");
+
+    let req = Request::get("/path").header(http::header::HOST, "[::1]:80").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "___1_-id");
+
+    let req = Request::get("/path").header(http::header::HOST, "bad/host").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "bad_host-id");
+
+    let res = svc.oneshot(Request::get("/path").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "unknown-id");
+}
+
+#[cfg(feature = "std")]
+#[tokio::test]
+async fn should_recover_id_from_timeout_error() {
+    use core::time::Duration;
+    use tower::timeout::{TimeoutLayer, error::Elapsed};
+    use tower_http_req_id::{set_response_id, RequestIdOnErrorLayer};
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .layer(RequestIdOnErrorLayer::<String>::new())
+                                   .layer(TimeoutLayer::new(Duration::from_millis(10)))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       tokio::time::sleep(Duration::from_secs(5)).await;
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+
+    let error = svc.oneshot(Request::new(Body::empty())).await.unwrap_err();
+    assert!(error.error().is::<Elapsed>());
+    assert_eq!(error.id().expect("to have id"), TEST_ID);
+    assert_eq!(format!("{:?}", error), "ErrorWithId { id: Some(***), error: Elapsed(()) }");
+
+    //Outer error handler builds response out of error
+    let mut res = Response::new(Body::empty());
+    *res.status_mut() = http::StatusCode::GATEWAY_TIMEOUT;
+    set_response_id(&mut res, error.id().expect("to have id")).unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+}
+
+#[cfg(feature = "rand")]
+#[tokio::test]
+async fn should_generate_id_from_user_rng() {
+    use rand::SeedableRng;
+    use rand::rngs::{StdRng, mock::StepRng};
+    use tower_http_req_id::RngGenerator;
+
+    let gen = RngGenerator::new(StepRng::new(0, 1), 8);
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+
+    for expected in ["0000000000000000", "0100000000000000"] {
+        let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), expected);
+    }
+    //RNG is shared with clones
+    assert_eq!(IdGen::<String>::gen(&gen), "0200000000000000");
+
+    let first = RngGenerator::new(StdRng::seed_from_u64(42), 16);
+    let second = RngGenerator::new(StdRng::seed_from_u64(42), 16);
+    for _ in 0..10 {
+        let id = IdGen::<String>::gen(&first);
+        assert_eq!(id.len(), 32);
+        assert_eq!(id, IdGen::<String>::gen(&second));
+    }
+}
+
+#[tokio::test]
+async fn should_store_differently_configured_ids_under_distinct_tags() {
+    use tower_http_req_id::{RequestId, TaggedGenerator};
+
+    const EDGE: u8 = 1;
+    const UPSTREAM: u8 = 2;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, RequestId<String, EDGE>>::new(TaggedGenerator::new(ValueGenerator("edge"))))
+                                   .layer(GenerateRequestIdLayer::<_, RequestId<String, UPSTREAM>>::new(TaggedGenerator::new(ValueGenerator("upstream"))).header_name(http::HeaderName::from_static("x-upstream-id")))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let edge = req.extensions().get::<RequestId<String, EDGE>>().expect("edge id is not inserted");
+                                       let upstream = req.extensions().get::<RequestId<String, UPSTREAM>>().expect("upstream id is not inserted");
+                                       assert!(req.extensions().get::<String>().is_none());
+                                       assert!(req.extensions().get::<RequestId<String>>().is_none());
+                                       Ok::<_, Infallible>(Response::new(format!("{}/{}", edge, upstream)))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "edge");
+    assert_eq!(res.headers().get("x-upstream-id").expect("to have upstream id header"), "upstream");
+    assert_eq!(res.into_body(), "edge/upstream");
+
+    let req = Request::builder().header(HEADER_NAME, "client").header("x-upstream-id", "proxy").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "client/proxy");
+}
+
+#[tokio::test]
+async fn should_verify_checksummed_id() {
+    use tower_http_req_id::{ChecksummedGenerator, verify_checksum};
+
+    let gen = ChecksummedGenerator::new(ValueGenerator("abc123"));
+    let generated = IdGen::<String>::gen(&gen);
+    assert_eq!(generated.len(), 7);
+    assert!(generated.starts_with("abc123"));
+    assert!(verify_checksum(&generated));
+    assert!(!verify_checksum("abc123"));
+    assert!(!verify_checksum("x"));
+    assert!(!verify_checksum(""));
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::with_scheme(ChecksummedGenerator::new(ValueGenerator("abc123"))))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let client = IdGen::<String>::gen(&ChecksummedGenerator::new(ValueGenerator("client-42")));
+    let truncated = client[..client.len() - 1].to_owned();
+    let swapped = client.replace("42", "24");
+    for (id, expected) in [(client.as_str(), client.as_str()), (truncated.as_str(), generated.as_str()), (swapped.as_str(), generated.as_str())] {
+        let req = Request::builder().header(HEADER_NAME, id).body(Body::empty()).unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.into_body(), expected);
+    }
+}
+
+#[test]
+fn should_detect_any_corrupted_character_of_checksummed_id() {
+    use tower_http_req_id::{ChecksummedGenerator, verify_checksum};
+
+    const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz-";
+
+    let id = IdGen::<String>::gen(&ChecksummedGenerator::new(ValueGenerator("123e4567-e89b-12d3-a456-426614174000")));
+    assert_eq!(id.len(), 37);
+    assert!(verify_checksum(&id));
+
+    for idx in 0..id.len() {
+        for symbol in ALPHABET.iter().filter(|symbol| **symbol != id.as_bytes()[idx]) {
+            let mut corrupted = id.clone().into_bytes();
+            corrupted[idx] = *symbol;
+            let corrupted = String::from_utf8(corrupted).unwrap();
+            assert!(!verify_checksum(&corrupted), "corruption '{}' is not detected", corrupted);
+        }
+
+        if idx + 1 < id.len() && id.as_bytes()[idx] != id.as_bytes()[idx + 1] {
+            let mut swapped = id.clone().into_bytes();
+            swapped.swap(idx, idx + 1);
+            let swapped = String::from_utf8(swapped).unwrap();
+            assert!(!verify_checksum(&swapped), "swap '{}' is not detected", swapped);
+        }
+    }
+}
+
+#[tokio::test]
+async fn should_count_generated_ids() {
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator);
+    assert_eq!(layer.generated_count(), None);
+
+    let layer = layer.count_generated();
+    let svc = ServiceBuilder::new().layer(layer.clone())
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+    assert_eq!(svc.generated_count(), Some(0));
+
+    for _ in 0..3 {
+        svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    }
+    for _ in 0..2 {
+        let req = Request::builder().header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+        svc.clone().oneshot(req).await.unwrap();
+    }
+    svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+
+    assert_eq!(svc.generated_count(), Some(4));
+    assert_eq!(layer.generated_count(), Some(4));
+}
+
+#[tokio::test]
+async fn should_round_trip_structured_field_id() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).structured_field())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let req = Request::builder().header(HEADER_NAME, r#""client \"a\"";origin=edge"#).body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), r#""client \"a\"""#);
+    assert_eq!(res.into_body(), r#"client "a""#);
+
+    let req = Request::builder().header(HEADER_NAME, "token-1").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.into_body(), "token-1");
+
+    for malformed in [r#""unterminated"#, r#""a" "b""#, "(list)", "a,b"] {
+        let req = Request::builder().header(HEADER_NAME, malformed).body(Body::empty()).unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), r#""id""#);
+        assert_eq!(res.into_body(), TEST_ID);
+    }
+}
+
+#[tokio::test]
+async fn should_derive_child_id_from_client_id() {
+    use tower_http_req_id::ParentId;
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).derive_child())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       let body = match req.extensions().get::<ParentId<String>>() {
+                                           Some(parent) => format!("{}/{}", parent, id),
+                                           None => id.to_owned(),
+                                       };
+                                       Ok::<_, Infallible>(Response::new(body))
+                                   }));
+
+    let req = Request::builder().header(HEADER_NAME, "parent").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+    assert_eq!(res.into_body(), format!("parent/{}", TEST_ID));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_read_and_write_different_header_names() {
+    const TRACE_HEADER: &str = "x-amzn-trace-id";
+
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator).request_header_name(http::HeaderName::from_static(TRACE_HEADER));
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let req = Request::builder().header(TRACE_HEADER, "Root=1-abc").header(HEADER_NAME, "ignored").body(Body::empty()).unwrap();
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert!(res.headers().get(TRACE_HEADER).is_none());
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "Root=1-abc");
+    assert_eq!(res.into_body(), "Root=1-abc");
+
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator).response_header_name(http::HeaderName::from_static("x-internal-id"));
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let req = Request::builder().header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(res.headers().get("x-internal-id").expect("to have internal id header"), "client");
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn should_count_generated_and_accepted_ids_via_metrics() {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use tower_http_req_id::{ACCEPTED_COUNTER, GENERATED_COUNTER};
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    metrics::with_local_recorder(&recorder, || {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        rt.block_on(async {
+            let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                           .service(service_fn(|_: Request<Body>| async move {
+                                               Ok::<_, Infallible>(Response::new(Body::empty()))
+                                           }));
+
+            for _ in 0..3 {
+                svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+            }
+            for _ in 0..2 {
+                let req = Request::builder().header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+                svc.clone().oneshot(req).await.unwrap();
+            }
+        });
+    });
+
+    let counters = snapshotter.snapshot().into_vec().into_iter().map(|(key, _, _, value)| {
+        assert_eq!(key.key().labels().count(), 0);
+        match value {
+            DebugValue::Counter(value) => (key.key().name().to_owned(), value),
+            _ => panic!("unexpected metric {:?}", key),
+        }
+    }).collect::<std::collections::HashMap<_, _>>();
+    assert_eq!(counters[GENERATED_COUNTER], 3);
+    assert_eq!(counters[ACCEPTED_COUNTER], 2);
+}
+
+#[cfg(feature = "uuid")]
+#[tokio::test]
+async fn should_store_id_string_regardless_of_type() {
+    use tower_http_req_id::{RequestIdStr, Uuid, UuidGenerator};
+
+    //Logging middleware, which doesn't know ID's type
+    fn log_id<B>(req: &Request<B>) -> String {
+        req.extensions().get::<RequestIdStr>().expect("id string is not inserted").as_str().to_owned()
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, Uuid>::new(UuidGenerator::new()).also_store_string())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<Uuid>().expect("required-id is not inserted");
+                                       assert_eq!(log_id(&req), id.to_string());
+                                       Ok::<_, Infallible>(Response::new(log_id(&req)))
+                                   }));
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    let header = res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap().to_owned();
+    assert_eq!(res.into_body(), header);
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, Uuid>::new(UuidGenerator::new()))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       assert!(req.extensions().get::<RequestIdStr>().is_none());
+                                       Ok::<_, Infallible>(Response::new(Body::empty()))
+                                   }));
+    svc.oneshot(Request::new(Body::empty())).await.unwrap();
+}
+
+#[cfg(feature = "std")]
+#[tokio::test]
+async fn should_limit_generation_rate() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).generation_limit(3))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    for _ in 0..3 {
+        let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.into_body(), TEST_ID);
+    }
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::TOO_MANY_REQUESTS);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+
+    for _ in 0..5 {
+        let req = Request::builder().header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.into_body(), "client");
+    }
+
+    //Child ID of client's ID is generated regardless of limit
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator).derive_child().generation_limit(1))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let parent = req.extensions().get::<tower_http_req_id::ParentId<String>>().map(|parent| parent.0.clone());
+                                       Ok::<_, Infallible>(Response::new(parent.unwrap_or_default()))
+                                   }));
+
+    for _ in 0..5 {
+        let req = Request::builder().header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+        assert_eq!(res.into_body(), "client");
+    }
+
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[cfg(feature = "std")]
+#[tokio::test]
+async fn should_generate_id_as_usual_when_rejection_is_not_possible() {
+    use tower_http_req_id::Rejection;
+
+    #[derive(Clone)]
+    struct NeverReject;
+
+    impl<B> Rejection<B> for NeverReject {
+        fn body(_: http::StatusCode) -> Option<B> {
+            None
+        }
+    }
+
+    let layer = GenerateRequestIdLayer::<_, String>::new(CounterGenerator::default()).generation_limit(1)
+                                                                                     .count_generated()
+                                                                                     .map_id(|id| format!("mapped-{}", id))
+                                                                                     .with_rejection::<NeverReject>();
+    let svc = ServiceBuilder::new().layer(layer.clone())
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    for expected in ["mapped-1", "mapped-2"] {
+        let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), expected);
+        assert_eq!(res.into_body(), expected);
+    }
+    assert_eq!(layer.generated_count(), Some(2));
+}
+
+#[tokio::test]
+async fn should_select_generator_from_registry_by_name() {
+    use tower_http_req_id::GeneratorRegistry;
+
+    let mut registry = GeneratorRegistry::new();
+    assert!(registry.register("test", TestGenerator).is_none());
+    assert!(registry.register("value", ValueGenerator("value")).is_none());
+    assert_eq!(registry.names().collect::<Vec<_>>(), ["test", "value"]);
+    assert!(registry.layer("unknown").is_none());
+
+    for (name, expected) in [("test", TEST_ID), ("value", "value")] {
+        let layer = registry.layer(name).expect("generator to be registered");
+        let svc = ServiceBuilder::new().layer(layer)
+                                       .service(service_fn(|req: Request<Body>| async move {
+                                           let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                           Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                       }));
+        let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), expected);
+        assert_eq!(res.into_body(), expected);
+    }
+
+    assert!(registry.register("value", ValueGenerator("replaced")).is_some());
+    assert_eq!(IdGen::gen(&registry.get("value").expect("generator to be registered")), "replaced");
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn should_skip_or_encode_generated_id_with_invalid_header_bytes() {
+    use tower_http_req_id::InvalidValue;
+    use tower_http_req_id::test_util::{Fault, FaultyGenerator};
+
+    let gen = FaultyGenerator::new(TEST_ID).fault(Fault::InvalidHeaderBytes);
+    let expected = [(InvalidValue::Skip, None), (InvalidValue::Encode, Some("id%0D%0A"))];
+    for (on_invalid_value, header) in expected {
+        let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen.clone()).on_invalid_value(on_invalid_value))
+                                       .service(service_fn(|req: Request<Body>| async move {
+                                           let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                           Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                       }));
+
+        let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).map(|header| header.to_str().unwrap()), header);
+        assert_eq!(res.into_body(), "id\r\n");
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn should_retry_empty_generated_id() {
+    use tower_http_req_id::RetryGen;
+    use tower_http_req_id::test_util::{Fault, FaultyGenerator};
+
+    let gen = FaultyGenerator::new(TEST_ID).fault(Fault::Empty).times(2);
+    let gen = RetryGen::new(gen, |id: &String| !id.is_empty(), 3);
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(gen))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn should_truncate_too_long_generated_id() {
+    use tower_http_req_id::{FsSafeGenerator, is_fs_safe};
+    use tower_http_req_id::test_util::{Fault, FaultyGenerator};
+
+    let gen = FaultyGenerator::new(TEST_ID).fault(Fault::TooLong(1024));
+    assert_eq!(IdGen::<String>::gen(&gen).len(), 1024);
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(FsSafeGenerator::new(gen)).with_value_capacity(TEST_ID.len()))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    let header = res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap().to_owned();
+    assert_eq!(header.len(), 255);
+    assert!(is_fs_safe(&header));
+    assert!(header.starts_with("idxxx"));
+    assert_eq!(res.into_body(), header);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn should_not_be_ready_while_generator_is_unavailable() {
+    use tower_http_req_id::test_util::{Fault, FaultyGenerator};
+
+    let unavailable = FaultyGenerator::new(TEST_ID).fault(Fault::Unavailable);
+    let mut svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(unavailable))
+                                       .service(service_fn(|_: Request<Body>| async move {
+                                           Ok::<_, Infallible>(Response::new(Body::empty()))
+                                       }));
+    let ready = tokio::time::timeout(core::time::Duration::from_millis(10), svc.ready()).await;
+    assert!(ready.is_err());
+
+    let recovering = FaultyGenerator::new(TEST_ID).fault(Fault::Unavailable).times(2);
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(recovering))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+    assert_eq!(res.into_body(), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_format_response_value_into_custom_sink() {
+    use core::cell::RefCell;
+    use core::fmt::{self, Write};
+    use hyper::body::Bytes;
+
+    thread_local! {
+        static SCRATCH: RefCell<String> = RefCell::new(String::with_capacity(64));
+    }
+
+    fn sink(len_hint: usize, args: fmt::Arguments<'_>) -> Bytes {
+        SCRATCH.with(|scratch| {
+            let mut scratch = scratch.borrow_mut();
+            scratch.clear();
+            scratch.reserve(len_hint);
+            scratch.write_fmt(args).expect("to write into String");
+            Bytes::copy_from_slice(scratch.as_bytes())
+        })
+    }
+
+    let scratch_ptr = SCRATCH.with(|scratch| scratch.borrow().as_ptr());
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(CounterGenerator::default()).zero_pad(4).value_sink(sink))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    //Sink receives ID as it is, while padding is applied to returned bytes
+    for (formatted, expected) in [("1", "0001"), ("2", "0002")] {
+        let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), expected);
+        SCRATCH.with(|scratch| {
+            let scratch = scratch.borrow();
+            assert_eq!(*scratch, formatted);
+            assert_eq!(scratch.as_ptr(), scratch_ptr);
+        });
+    }
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("42"));
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "0042");
+    SCRATCH.with(|scratch| assert_eq!(*scratch.borrow(), "42"));
+}
+
+#[tokio::test]
+async fn should_share_id_per_connection() {
+    use tower::Service;
+
+    let mut svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(CounterGenerator::default()).per_connection())
+                                       .service(service_fn(|req: Request<Body>| async move {
+                                           let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                           Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                       }));
+    let mut connection = svc.clone();
+
+    for _ in 0..2 {
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+        let res = connection.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "1");
+        assert_eq!(res.into_body(), "1");
+    }
+
+    let mut other_connection = connection.clone();
+    for _ in 0..2 {
+        let res = other_connection.ready().await.unwrap().call(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "2");
+        assert_eq!(res.into_body(), "2");
+    }
+
+    let res = svc.ready().await.unwrap().call(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.into_body(), "3");
+}
+
+#[tokio::test]
+async fn should_require_header_per_connection() {
+    use tower::Service;
+
+    for reject_invalid in [false, true] {
+        let layer = GenerateRequestIdLayer::<_, String>::new(CounterGenerator::default()).per_connection();
+        let (layer, invalid_status) = match reject_invalid {
+            false => (layer.require_header(http::StatusCode::BAD_REQUEST), http::StatusCode::OK),
+            true => (layer.require_valid_header(http::StatusCode::BAD_REQUEST), http::StatusCode::BAD_REQUEST),
+        };
+        let mut connection = ServiceBuilder::new().layer(layer)
+                                                  .service(service_fn(|req: Request<Body>| async move {
+                                                      let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                                      Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                                  }));
+
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+        let res = connection.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.into_body(), "1");
+
+        //ID is already generated for connection, but requirement still applies
+        let res = connection.ready().await.unwrap().call(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+        assert!(res.headers().get(HEADER_NAME).is_none());
+
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(" "));
+        let res = connection.ready().await.unwrap().call(req).await.unwrap();
+        assert_eq!(res.status(), invalid_status);
+    }
+}
+
+#[cfg(feature = "std")]
+#[tokio::test]
+async fn should_map_inner_error_with_id() {
+    use tower_http_req_id::RequestIdError;
+
+    let error = GenerateRequestIdLayer::<_, String>::new(TestGenerator).skip_request_extension().map_error_with_id().err();
+    assert!(matches!(error, Some(RequestIdError::ExtensionDisabled)));
+
+    let layer = match GenerateRequestIdLayer::<_, String>::new(TestGenerator).map_error_with_id() {
+        Ok(layer) => layer,
+        Err(error) => panic!("unable to map error: {}", error),
+    };
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       match req.uri().path() {
+                                           "/error" => Err("failed"),
+                                           _ => Ok(Response::new(Body::empty())),
+                                       }
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    *req.uri_mut() = http::Uri::from_static("/error");
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static("client"));
+    let error = svc.clone().oneshot(req).await.unwrap_err();
+    assert_eq!(*error.error(), "failed");
+    assert_eq!(error.id().expect("to have id"), "client");
+    assert_eq!(error.into_parts(), (Some("client".to_owned()), "failed"));
+
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+}
+
+#[tokio::test]
+async fn should_not_require_default_body_unless_rejecting() {
+    use tower_http_req_id::Rejection;
+
+    //Body without `Default`
+    #[derive(Debug, PartialEq)]
+    struct Text(&'static str);
+
+    struct TextRejection;
+
+    impl Rejection<Text> for TextRejection {
+        fn body(status: http::StatusCode) -> Option<Text> {
+            status.canonical_reason().map(Text)
+        }
+    }
+
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new(TestGenerator))
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Text("ok")))
+                                   }));
+    let res = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), TEST_ID);
+    assert_eq!(*res.body(), Text("ok"));
+
+    let layer = GenerateRequestIdLayer::<_, String>::new(TestGenerator).require_header(http::StatusCode::BAD_REQUEST).with_rejection::<TextRejection>();
+    let svc = ServiceBuilder::new().layer(layer)
+                                   .service(service_fn(|_: Request<Body>| async move {
+                                       Ok::<_, Infallible>(Response::new(Text("ok")))
+                                   }));
+    let res = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+    assert!(res.headers().get(HEADER_NAME).is_none());
+    assert_eq!(*res.body(), Text("Bad Request"));
+
+    let req = Request::builder().header(HEADER_NAME, "client").body(Body::empty()).unwrap();
+    let res = svc.oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header"), "client");
+    assert_eq!(*res.body(), Text("ok"));
+}
+
+#[tokio::test]
+async fn should_convert_string_id_directly_with_infallible_layer() {
+    let svc = ServiceBuilder::new().layer(GenerateRequestIdLayer::<_, String>::new_infallible(TestGenerator))
+                                   .service(service_fn(|req: Request<Body>| async move {
+                                       let id = req.extensions().get::<String>().expect("required-id is not inserted");
+                                       Ok::<_, Infallible>(Response::new(id.to_owned()))
+                                   }));
+
+    let mut req = Request::new(Body::empty());
+    req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_static(" client "));
+    let res = svc.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), "client");
+    assert_eq!(res.into_body(), "client");
+
+    for value in [&b"\xff\xfe"[..], "ид".as_bytes(), b""] {
+        let mut req = Request::new(Body::empty());
+        req.headers_mut().insert(HEADER_NAME, http::HeaderValue::from_bytes(value).unwrap());
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.headers().get(HEADER_NAME).expect("to have request-id header").to_str().unwrap(), TEST_ID);
+        assert_eq!(res.into_body(), TEST_ID);
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(feature = "uuid")]
+use tower_http_req_id::{IdGen, UuidGenerator};
+
+#[cfg(feature = "uuid")]
+use std::collections::HashSet;
+#[cfg(feature = "uuid")]
+use core::hash::Hash;
+
+#[cfg(all(feature = "test-util", any(feature = "uuid", feature = "random", feature = "b3")))]
+const IDS: usize = 10_000;
+#[cfg(feature = "uuid")]
+const THREADS: usize = 8;
+#[cfg(feature = "uuid")]
+const IDS_PER_THREAD: usize = 2000;
+
+#[cfg(feature = "uuid")]
+fn assert_unique_across_threads<G: IdGen<O> + Sync, O: Eq + Hash + Send + core::fmt::Debug>(gen: &G) {
+    let ids = std::thread::scope(|scope| {
+        let workers = (0..THREADS).map(|_| scope.spawn(|| (0..IDS_PER_THREAD).map(|_| gen.gen()).collect::<Vec<_>>()))
+                                  .collect::<Vec<_>>();
+        workers.into_iter().flat_map(|worker| worker.join().expect("worker to finish")).collect::<Vec<_>>()
+    });
+
+    let mut unique = HashSet::with_capacity(ids.len());
+    for id in ids {
+        assert!(unique.insert(id), "duplicate id generated");
+    }
+    assert_eq!(unique.len(), THREADS * IDS_PER_THREAD);
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn should_generate_unique_uuid_v1_concurrently() {
+    let gen = UuidGenerator::new();
+    if !gen.is_v1() {
+        return;
+    }
+
+    assert_unique_across_threads::<_, tower_http_req_id::Uuid>(&gen);
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn should_generate_unique_uuid_v4_concurrently() {
+    assert_unique_across_threads::<_, tower_http_req_id::Uuid>(&UuidGenerator::new_v4());
+}
+
+#[cfg(all(feature = "uuid", feature = "test-util"))]
+#[test]
+fn should_generate_unique_uuid() {
+    use tower_http_req_id::test_util::assert_unique;
+
+    let gen = UuidGenerator::new();
+    assert_unique::<_, tower_http_req_id::Uuid>(&gen, IDS).expect("uuid collision");
+    assert_unique::<_, tower_http_req_id::Uuid>(&UuidGenerator::new_v4(), IDS).expect("uuid v4 collision");
+}
+
+#[cfg(all(feature = "random", feature = "test-util"))]
+#[test]
+fn should_generate_unique_base62() {
+    use tower_http_req_id::test_util::assert_unique;
+    use tower_http_req_id::{Base62Generator, SaltedCounterGenerator};
+
+    assert_unique::<_, String>(&Base62Generator::new(16), IDS).expect("base62 collision");
+    assert_unique::<_, String>(&SaltedCounterGenerator::new(), IDS).expect("salted counter collision");
+}
+
+#[cfg(all(feature = "random", feature = "test-util"))]
+#[test]
+fn should_generate_unique_fixed_hex() {
+    use tower_http_req_id::test_util::assert_unique;
+    use tower_http_req_id::{IdGen, FixedHexGenerator, RawId};
+
+    let gen = FixedHexGenerator::<16>::new();
+    for _ in 0..100 {
+        let id = IdGen::<String>::gen(&gen);
+        assert_eq!(id.len(), 32);
+        assert!(id.bytes().all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte)));
+        assert_eq!(id.parse::<RawId<16>>().expect("to parse hex").to_string(), id);
+    }
+
+    assert_unique::<_, String>(&gen, IDS).expect("fixed hex collision");
+    assert_unique::<_, RawId<16>>(&gen, IDS).expect("raw id collision");
+    assert!("0123".parse::<RawId<16>>().is_err());
+    assert!("zz".parse::<RawId<1>>().is_err());
+    assert_eq!("0aFf".parse::<RawId<2>>(), Ok(RawId([0x0a, 0xff])));
+    assert_eq!(format!("{:0>6}", RawId([0x0a, 0xff])), "000aff");
+}
+
+#[cfg(all(feature = "b3", feature = "test-util"))]
+#[test]
+fn should_generate_unique_b3() {
+    use tower_http_req_id::test_util::assert_unique;
+    use tower_http_req_id::{B3, B3Generator};
+
+    assert_unique::<_, B3>(&B3Generator, IDS).expect("b3 collision");
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn should_report_collision() {
+    use tower_http_req_id::test_util::assert_unique;
+    use tower_http_req_id::{StaticId, StaticGenerator};
+
+    assert_eq!(assert_unique::<_, StaticId>(&StaticGenerator::new("static"), 1), Ok(()));
+    assert_eq!(assert_unique::<_, StaticId>(&StaticGenerator::new("static"), 2), Err(StaticId("static")));
+}